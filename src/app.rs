@@ -5,8 +5,13 @@ use
     winit::{window::*, event::*, event_loop::*},
     super::
     {
+        utility::Waker,
         loader::*,
         interface::*,
+        painters::Tone,
+        navigator::{NavigatorOptions, FilepathsSource},
+        decoders::DecoderRegistry,
+        picture::{self, PlaybackControl, PictureFrame},
         reader::*
     }
 };
@@ -16,12 +21,18 @@ use
 pub struct App
 {
     interface: Option<Interface>,
-    reader: Option<PictureDirectoryReader> 
+    reader: Option<PictureDirectoryReader>,
+    options: NavigatorOptions,
+    modifiers: ModifiersState,
+    dropped: Vec<PathBuf>,
+    waker: Waker
 }
 
 impl App
 {
-    pub fn new<P: AsRef<Path>>(path: P) -> anyhow::Result
+    const EXPOSURE_STEP: f32 = 0.5;
+
+    pub fn new(source: FilepathsSource) -> anyhow::Result
     <(
         Self,
         winit::event_loop::EventLoop<()>
@@ -32,9 +43,18 @@ impl App
         let mut this = Self
         {
             interface: Some(interface),
-            reader: None
+            reader: None,
+            options: NavigatorOptions::new(DecoderRegistry::global().extensions()),
+            modifiers: ModifiersState::empty(),
+            dropped: vec![],
+            waker: Waker::new(event_loop.create_proxy())
         };
-        this.reader = match PictureDirectoryReader::new(path)
+        this.reader = match PictureDirectoryReader::new
+        (
+            source,
+            this.options.clone(),
+            this.waker.clone()
+        )
         {
             Ok(reader) => Some(reader),
             Err(error) =>
@@ -75,6 +95,24 @@ impl App
                         self.disable_interaction()?;
                         Ok(control_flow.set_exit())
                     }
+                    VirtualKeyCode::Left | VirtualKeyCode::Right
+                        if self.modifiers.ctrl() => match self.reader.as_mut().map
+                    (
+                        |reader| reader.change_directory
+                        (
+                            match keycode
+                            {
+                                VirtualKeyCode::Left => -1,
+                                VirtualKeyCode::Right => 1,
+                                _ => unreachable!()
+                            }
+                        )
+                    )
+                    {
+                        Some(Ok(true)) => self.disable_interaction(),
+                        Some(Err(error)) => self.show_error(&error),
+                        Some(Ok(false)) | None => Ok(())
+                    }
                     VirtualKeyCode::Left | VirtualKeyCode::Right
                         => match self.reader.take()
                     {
@@ -107,20 +145,140 @@ impl App
                             .as_mut().unwrap()
                             .toggle_always_on_top()
                     ),
-                    _ => Ok(())
-                }
-                WindowEvent::DroppedFile(path) => match self.reader.take()
-                {
-                    Some(reader) => match reader.change_path(path)
+                    VirtualKeyCode::Equals | VirtualKeyCode::Plus |
+                    VirtualKeyCode::NumpadAdd |
+                    VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract =>
                     {
-                        Ok(reader) => Ok(self.reader = Some(reader)),
-                        Err(error) => Ok(self.show_error(&error)?)
+                        let interface = self.interface.as_mut().unwrap();
+                        let mut tone = interface.get_tone();
+                        tone.exposure += match keycode
+                        {
+                            VirtualKeyCode::Minus |
+                            VirtualKeyCode::NumpadSubtract => -Self::EXPOSURE_STEP,
+                            _ => Self::EXPOSURE_STEP
+                        };
+                        interface.set_tone(tone);
+                        Ok(())
                     }
-                    None => match PictureDirectoryReader::new(path)
+                    VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 =>
                     {
-                        Ok(reader) => Ok(self.reader = Some(reader)),
-                        Err(error) => Ok(self.show_error(&error)?)
+                        let interface = self.interface.as_mut().unwrap();
+                        let tone = Tone
+                        {
+                            exposure: 0.0,
+                            ..interface.get_tone()
+                        };
+                        interface.set_tone(tone);
+                        Ok(())
                     }
+                    VirtualKeyCode::Space | VirtualKeyCode::Period |
+                    VirtualKeyCode::Comma | VirtualKeyCode::LBracket |
+                    VirtualKeyCode::RBracket => match &mut self.reader
+                    {
+                        Some(reader) =>
+                        {
+                            reader.control_playback
+                            (
+                                match keycode
+                                {
+                                    VirtualKeyCode::Space => PlaybackControl::TogglePause,
+                                    VirtualKeyCode::Period => PlaybackControl::StepForward,
+                                    VirtualKeyCode::Comma => PlaybackControl::StepBackward,
+                                    VirtualKeyCode::LBracket => PlaybackControl::SlowDown,
+                                    VirtualKeyCode::RBracket => PlaybackControl::SpeedUp,
+                                    _ => unreachable!()
+                                }
+                            );
+                            Ok(())
+                        }
+                        None => Ok(())
+                    }
+                    VirtualKeyCode::PageUp | VirtualKeyCode::PageDown => match &mut self.reader
+                    {
+                        Some(reader) =>
+                        {
+                            reader.turn_page
+                            (
+                                match keycode
+                                {
+                                    VirtualKeyCode::PageUp => -1,
+                                    _ => 1
+                                }
+                            );
+                            Ok(())
+                        }
+                        None => Ok(())
+                    }
+                    VirtualKeyCode::E =>
+                    {
+                        self.options.sniffer = match self.options.sniffer
+                        {
+                            Some(..) => None,
+                            None => Some(picture::sniff_picture)
+                        };
+                        match self.reader.take()
+                        {
+                            Some(reader) => match reader.set_options(self.options.clone())
+                            {
+                                Ok(reader) =>
+                                {
+                                    self.reader = Some(reader);
+                                    Ok(())
+                                }
+                                Err(error) => Ok(self.show_error(&error)?)
+                            }
+                            None => Ok(())
+                        }
+                    }
+                    VirtualKeyCode::R =>
+                    {
+                        self.options.recursive = !self.options.recursive;
+                        match self.reader.take()
+                        {
+                            Some(reader) => match reader.set_options(self.options.clone())
+                            {
+                                Ok(reader) =>
+                                {
+                                    self.reader = Some(reader);
+                                    Ok(())
+                                }
+                                Err(error) => Ok(self.show_error(&error)?)
+                            }
+                            None => Ok(())
+                        }
+                    }
+                    VirtualKeyCode::S =>
+                    {
+                        self.options.order = self.options.order.next();
+                        match &mut self.reader
+                        {
+                            Some(reader) =>
+                            {
+                                reader.sort(self.options.order);
+                                Ok(())
+                            }
+                            None => Ok(())
+                        }
+                    }
+                    VirtualKeyCode::M =>
+                    {
+                        let interface = self.interface.as_mut().unwrap();
+                        let mut tone = interface.get_tone();
+                        tone.mapping = tone.mapping.next();
+                        interface.set_tone(tone);
+                        Ok(())
+                    }
+                    _ => Ok(())
+                }
+                WindowEvent::ModifiersChanged(modifiers) =>
+                {
+                    self.modifiers = modifiers;
+                    Ok(())
+                }
+                WindowEvent::DroppedFile(path) =>
+                {
+                    self.dropped.push(path);
+                    Ok(())
                 }
                 WindowEvent::MouseInput
                 {
@@ -143,6 +301,30 @@ impl App
         Ok(())
     }
 
+    fn open_dropped(&mut self) -> anyhow::Result<()>
+    {
+        let source = FilepathsSource::from_paths(std::mem::take(&mut self.dropped));
+        let reader = match self.reader.take()
+        {
+            Some(reader) => reader.change_source(source),
+            None => PictureDirectoryReader::new
+            (
+                source,
+                self.options.clone(),
+                self.waker.clone()
+            )
+        };
+        match reader
+        {
+            Ok(reader) =>
+            {
+                self.reader = Some(reader);
+                Ok(())
+            }
+            Err(error) => self.show_error(&error)
+        }
+    }
+
     fn disable_interaction(&mut self) -> anyhow::Result<()>
     {
         let interface = self.interface
@@ -160,29 +342,50 @@ impl App
         Ok(self.interface = Some(interface))
     }
 
-    pub fn refresh(&mut self) -> anyhow::Result<()>
+    pub fn refresh(&mut self, control_flow: &mut ControlFlow) -> anyhow::Result<()>
     {
+        control_flow.set_wait();
+        if !self.dropped.is_empty()
+        {
+            self.open_dropped()?
+        }
         if let Some(reader) = self.reader.take()
         {
             match reader.refresh_filepaths()
             {
                 Ok(mut reader) =>
                 {
-                    if let Some(result) = reader.next()
+                    let result = reader.next();
+                    match (result.is_some(), reader.deadline())
                     {
-                        let mut interface = self.interface
-                            .take().unwrap();
-                        interface = match result
-                        {
-                            PictureLoadResult::PictureError(error)
-                                => interface.show_error(&error)?,
-                            PictureLoadResult::Loading(dimensions)
-                                => interface.show_blank(dimensions)?,
-                            PictureLoadResult::Loaded(still)
-                                => interface.show_picture(still)?
-                        };
-                        self.interface = Some(interface)
+                        (true, _) => control_flow.set_poll(),
+                        (false, Some(deadline)) => control_flow.set_wait_until(deadline),
+                        (false, None) => {}
                     }
+                    let mut interface = self.interface
+                        .take().unwrap();
+                    let changed = interface
+                        .set_picture_status(reader.picture_status());
+                    interface = match result
+                    {
+                        Some(PictureLoadResult::PictureError(error))
+                            => interface.show_error(&error)?,
+                        Some(PictureLoadResult::Loading(dimensions))
+                            => interface.show_blank(dimensions)?,
+                        Some(PictureLoadResult::Preview(still) | PictureLoadResult::Partial(still))
+                            => interface.show_picture(PictureFrame::Still(still.into()))?,
+                        Some(PictureLoadResult::Loaded(frame))
+                            => interface.show_picture(frame)?,
+                        None =>
+                        {
+                            if changed
+                            {
+                                interface.draw()
+                            }
+                            interface
+                        }
+                    };
+                    self.interface = Some(interface);
                     self.reader = Some(reader)
                 }
                 Err(error) => self.show_error(&error)?
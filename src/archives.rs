@@ -0,0 +1,541 @@
+
+use
+{
+    std::{fs, io::{self, Read, Seek, SeekFrom}, path::*, sync::{Arc, Mutex}, time::SystemTime},
+    super::{containers::invalid_data, picture::DecodeBudget}
+};
+
+// ------------------------------------------------------------
+
+fn read_u16_le(bytes: &[u8], offset: usize) -> io::Result<u16>
+{
+    bytes.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid_data("Truncated archive header"))
+}
+
+fn read_u32_le(bytes: &[u8], offset: usize) -> io::Result<u32>
+{
+    bytes.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid_data("Truncated archive header"))
+}
+
+fn bounded(size: u64, offset: u64, length: u64) -> io::Result<usize>
+{
+    match size <= length.saturating_sub(offset)
+    {
+        true => Ok(size as usize),
+        false => Err(invalid_data("Archive entry extends past the end of the archive"))
+    }
+}
+
+fn within_budget(size: u64) -> io::Result<u64>
+{
+    let budget = DecodeBudget::from_env();
+    match size <= budget.bytes
+    {
+        true => Ok(size),
+        false => Err(invalid_data(format!("Archive entry exceeds the decoding budget of {budget}")))
+    }
+}
+
+fn entry_name(relative: &Path) -> Option<String>
+{
+    let mut parts = vec![];
+    for component in relative.components()
+    {
+        match component
+        {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            _ => return None
+        }
+    }
+    Some(parts.join("/"))
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+enum Location
+{
+    Zip{header: u64, method: u16, compressed: u64, size: u64},
+    Tar{offset: u64, size: u64}
+}
+
+struct Entry
+{
+    name: String,
+    location: Location
+}
+
+fn zip_entries<R>(reader: &mut R) -> io::Result<Vec<Entry>>
+where R: Read + Seek
+{
+    const END_SIGNATURE: u32 = 0x06054b50;
+    const CENTRAL_SIGNATURE: u32 = 0x02014b50;
+    const END_SIZE: usize = 22;
+    const ENCRYPTED: u16 = 1;
+    let length = reader.seek(SeekFrom::End(0))?;
+    let tail_length = length.min((END_SIZE + u16::MAX as usize) as u64);
+    reader.seek(SeekFrom::Start(length - tail_length))?;
+    let mut tail = vec![0; tail_length as usize];
+    reader.read_exact(&mut tail)?;
+    let end = (0..(tail.len() + 1).saturating_sub(END_SIZE)).rev()
+        .find(|&offset| read_u32_le(&tail, offset).ok() == Some(END_SIGNATURE))
+        .ok_or_else(|| invalid_data("Missing zip central directory"))?;
+    let count = read_u16_le(&tail, end + 10)?;
+    let directory_size = read_u32_le(&tail, end + 12)?;
+    let directory_offset = read_u32_le(&tail, end + 16)?;
+    if count == u16::MAX || directory_offset == u32::MAX
+    {
+        return Err(invalid_data("Zip64 archives are not supported"))
+    }
+    reader.seek(SeekFrom::Start(directory_offset as u64))?;
+    let mut directory = vec![0; bounded(directory_size as u64, directory_offset as u64, length)?];
+    reader.read_exact(&mut directory)?;
+    let mut entries = vec![];
+    let mut position = 0;
+    for _ in 0..count
+    {
+        if read_u32_le(&directory, position)? != CENTRAL_SIGNATURE
+        {
+            return Err(invalid_data("Invalid zip central directory entry"))
+        }
+        let flags = read_u16_le(&directory, position + 8)?;
+        let method = read_u16_le(&directory, position + 10)?;
+        let compressed = read_u32_le(&directory, position + 20)? as u64;
+        let size = read_u32_le(&directory, position + 24)? as u64;
+        let name_length = read_u16_le(&directory, position + 28)? as usize;
+        let extra_length = read_u16_le(&directory, position + 30)? as usize;
+        let comment_length = read_u16_le(&directory, position + 32)? as usize;
+        let header = read_u32_le(&directory, position + 42)? as u64;
+        bounded(compressed, header, length)?;
+        let name = directory.get(position + 46..position + 46 + name_length)
+            .map(String::from_utf8_lossy)
+            .ok_or_else(|| invalid_data("Truncated zip entry name"))?
+            .into_owned();
+        position += 46 + name_length + extra_length + comment_length;
+        if flags & ENCRYPTED == 0 && !name.ends_with('/')
+        {
+            let location = Location::Zip{header, method, compressed, size};
+            entries.push(Entry{name, location})
+        }
+    }
+    Ok(entries)
+}
+
+fn zip_data<R>(reader: &mut R, header: u64, method: u16, compressed: u64, size: u64)
+    -> io::Result<Vec<u8>>
+where R: Read + Seek
+{
+    const LOCAL_SIGNATURE: u32 = 0x04034b50;
+    const STORED: u16 = 0;
+    const DEFLATED: u16 = 8;
+    let length = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(header))?;
+    let mut local = [0; 30];
+    reader.read_exact(&mut local)?;
+    if read_u32_le(&local, 0)? != LOCAL_SIGNATURE
+    {
+        return Err(invalid_data("Invalid zip local header"))
+    }
+    let skipped = read_u16_le(&local, 26)? as i64 + read_u16_le(&local, 28)? as i64;
+    let offset = reader.seek(SeekFrom::Current(skipped))?;
+    let mut data = vec![0; bounded(within_budget(compressed)?, offset, length)?];
+    reader.read_exact(&mut data)?;
+    match method
+    {
+        STORED => Ok(data),
+        DEFLATED => miniz_oxide::inflate
+            ::decompress_to_vec_with_limit(&data, within_budget(size)? as usize)
+            .map_err(|error| invalid_data(error.to_string())),
+        _ => Err(invalid_data(format!("Unsupported zip compression method {method}")))
+    }
+}
+
+// ------------------------------------------------------------
+
+fn tar_string(field: &[u8]) -> String
+{
+    let end = field.iter().position(|&byte| byte == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn tar_number(field: &[u8]) -> io::Result<u64>
+{
+    match field.split_first()
+    {
+        Some((first, rest)) if first & 0x80 != 0 => Ok
+        (
+            rest.iter().fold((first & 0x7f) as u64, |number, &byte| number << 8 | byte as u64)
+        ),
+        _ => match tar_string(field).trim_matches(' ')
+        {
+            "" => Ok(0),
+            digits => u64::from_str_radix(digits, 8).map_err(invalid_data)
+        }
+    }
+}
+
+fn pax_path(data: &[u8]) -> Option<String>
+{
+    String::from_utf8_lossy(data)
+        .split('\n')
+        .find_map(|record| record.split_once(' ')?.1.strip_prefix("path=").map(str::to_owned))
+}
+
+fn tar_entries<R>(reader: &mut R) -> io::Result<Vec<Entry>>
+where R: Read + Seek
+{
+    const BLOCK: u64 = 512;
+    let length = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+    let mut entries = vec![];
+    let mut long_name = None;
+    let mut header = [0; BLOCK as usize];
+    loop
+    {
+        match reader.read_exact(&mut header)
+        {
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+            result => result?
+        }
+        if header.iter().all(|&byte| byte == 0)
+        {
+            break
+        }
+        let checksum = header.iter().enumerate()
+            .map(|(index, &byte)| if (148..156).contains(&index) {b' '} else {byte} as u64)
+            .sum::<u64>();
+        if tar_number(&header[148..156])? != checksum
+        {
+            return Err(invalid_data("Invalid tar header checksum"))
+        }
+        let size = tar_number(&header[124..136])?;
+        let offset = reader.stream_position()?;
+        match header[156]
+        {
+            b'L' | b'x' =>
+            {
+                let mut data = vec![0; bounded(size, offset, length)?];
+                reader.read_exact(&mut data)?;
+                long_name = match header[156]
+                {
+                    b'L' => Some(tar_string(&data)),
+                    _ => pax_path(&data)
+                }
+            }
+            b'0' | b'\0' | b'7' =>
+            {
+                let name = long_name.take().unwrap_or_else
+                (
+                    || match (&header[257..262] == b"ustar", tar_string(&header[345..500]))
+                    {
+                        (true, prefix) if !prefix.is_empty()
+                            => format!("{prefix}/{}", tar_string(&header[..100])),
+                        _ => tar_string(&header[..100])
+                    }
+                );
+                bounded(size, offset, length)?;
+                entries.push(Entry{name, location: Location::Tar{offset, size}})
+            }
+            _ => long_name = None
+        }
+        reader.seek(SeekFrom::Start(offset + size.next_multiple_of(BLOCK)))?;
+    }
+    Ok(entries)
+}
+
+// ------------------------------------------------------------
+
+enum ArchiveKind
+{
+    Zip,
+    Tar
+}
+
+impl ArchiveKind
+{
+    fn of(path: &Path) -> Option<Self>
+    {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str()
+        {
+            "zip" | "cbz" => Some(Self::Zip),
+            "tar" => Some(Self::Tar),
+            _ => None
+        }
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool
+{
+    ArchiveKind::of(path).is_some()
+}
+
+fn read_entries<R>(reader: &mut R, archive: &Path) -> io::Result<Vec<Entry>>
+where R: Read + Seek
+{
+    match ArchiveKind::of(archive)
+    {
+        Some(ArchiveKind::Zip) => zip_entries(reader),
+        Some(ArchiveKind::Tar) => tar_entries(reader),
+        None => Err(invalid_data("Unsupported archive format"))
+    }
+}
+
+// ------------------------------------------------------------
+
+type Index = Arc<Vec<(String, Location)>>;
+
+struct IndexCache(Vec<(PathBuf, SystemTime, Index)>);
+
+static INDEXES: Mutex<IndexCache> = Mutex::new(IndexCache(Vec::new()));
+
+impl IndexCache
+{
+    const CAPACITY: usize = 4;
+
+    fn index(archive: &Path) -> io::Result<Index>
+    {
+        let modified = fs::metadata(archive)?.modified()?;
+        let cached = INDEXES.lock().unwrap().0.iter()
+            .find(|(path, time, _)| path == archive && *time == modified)
+            .map(|(.., index)| index.clone());
+        if let Some(index) = cached
+        {
+            return Ok(index)
+        }
+        let mut reader = fs::File::open(archive).map(io::BufReader::new)?;
+        let index: Index = Arc::new
+        (
+            read_entries(&mut reader, archive)?
+                .into_iter()
+                .filter_map
+                (
+                    |entry| entry_name(Path::new(&entry.name)).map(|name| (name, entry.location))
+                )
+                .collect()
+        );
+        let mut cache = INDEXES.lock().unwrap();
+        cache.0.retain(|(path, ..)| path != archive);
+        if cache.0.len() >= Self::CAPACITY
+        {
+            cache.0.remove(0);
+        }
+        cache.0.push((archive.to_owned(), modified, index.clone()));
+        Ok(index)
+    }
+}
+
+pub fn entries(archive: &Path) -> io::Result<Vec<PathBuf>>
+{
+    Ok
+    (
+        IndexCache::index(archive)?
+            .iter()
+            .map(|(name, _)| archive.join(name))
+            .collect()
+    )
+}
+
+pub fn read(filepath: &Path) -> io::Result<Option<Vec<u8>>>
+{
+    if filepath.is_file()
+    {
+        return Ok(None)
+    }
+    let archive = match filepath.ancestors()
+        .skip(1)
+        .find(|ancestor| is_archive(ancestor) && ancestor.is_file())
+    {
+        Some(archive) => archive,
+        None => return Ok(None)
+    };
+    let not_found = || io::Error::new(io::ErrorKind::NotFound, "Archive entry not found");
+    let name = filepath.strip_prefix(archive).ok()
+        .and_then(entry_name)
+        .ok_or_else(not_found)?;
+    let location = IndexCache::index(archive)?
+        .iter()
+        .find(|(entry, _)| entry == &name)
+        .map(|(_, location)| *location)
+        .ok_or_else(not_found)?;
+    let mut reader = fs::File::open(archive).map(io::BufReader::new)?;
+    read_data(&mut reader, location).map(Some)
+}
+
+fn read_data<R>(reader: &mut R, location: Location) -> io::Result<Vec<u8>>
+where R: Read + Seek
+{
+    match location
+    {
+        Location::Zip{header, method, compressed, size}
+            => zip_data(reader, header, method, compressed, size),
+        Location::Tar{offset, size} =>
+        {
+            let length = reader.seek(SeekFrom::End(0))?;
+            reader.seek(SeekFrom::Start(offset))?;
+            let mut data = vec![0; bounded(within_budget(size)?, offset, length)?];
+            reader.read_exact(&mut data)?;
+            Ok(data)
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn zip_archive(files: &[(&str, u16, &[u8], &[u8])]) -> Vec<u8>
+    {
+        let (mut bytes, mut directory) = (vec![], vec![]);
+        for &(name, method, stored, content) in files
+        {
+            let header = bytes.len() as u32;
+            bytes.extend(0x04034b50u32.to_le_bytes());
+            bytes.extend([0; 22]);
+            bytes.extend((name.len() as u16).to_le_bytes());
+            bytes.extend([0; 2]);
+            bytes.extend(name.as_bytes());
+            bytes.extend(stored);
+            directory.extend(0x02014b50u32.to_le_bytes());
+            directory.extend([0; 6]);
+            directory.extend(method.to_le_bytes());
+            directory.extend([0; 8]);
+            directory.extend((stored.len() as u32).to_le_bytes());
+            directory.extend((content.len() as u32).to_le_bytes());
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(header.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+        let offset = bytes.len() as u32;
+        bytes.extend(&directory);
+        bytes.extend(0x06054b50u32.to_le_bytes());
+        bytes.extend([0; 6]);
+        bytes.extend((files.len() as u16).to_le_bytes());
+        bytes.extend((directory.len() as u32).to_le_bytes());
+        bytes.extend(offset.to_le_bytes());
+        bytes.extend([0; 2]);
+        bytes
+    }
+
+    fn tar_header(name: &str, kind: u8, size: usize, prefix: &str) -> Vec<u8>
+    {
+        let mut header = vec![0; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{size:011o}").as_bytes());
+        header[156] = kind;
+        if !prefix.is_empty()
+        {
+            header[257..262].copy_from_slice(b"ustar");
+            header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+        }
+        header[148..156].fill(b' ');
+        let checksum = header.iter().map(|&byte| byte as u64).sum::<u64>();
+        header[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+        header
+    }
+
+    fn tar_file(bytes: &mut Vec<u8>, header: Vec<u8>, content: &[u8])
+    {
+        bytes.extend(header);
+        bytes.extend(content);
+        bytes.resize(bytes.len().next_multiple_of(512), 0)
+    }
+
+    fn names(entries: &[Entry]) -> Vec<&str>
+    {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn zip_entries_are_listed_and_read()
+    {
+        let deflated = miniz_oxide::deflate::compress_to_vec(b"deflated content", 6);
+        let bytes = zip_archive
+        (
+            &[
+                ("pictures/", 0, b"", b""),
+                ("pictures/a.png", 0, b"stored", b"stored"),
+                ("pictures/b.png", 8, &deflated, b"deflated content")
+            ]
+        );
+        let mut reader = io::Cursor::new(bytes);
+        let entries = zip_entries(&mut reader).unwrap();
+        assert_eq!(names(&entries), ["pictures/a.png", "pictures/b.png"]);
+        let contents: Vec<_> = entries.iter()
+            .map(|entry| read_data(&mut reader, entry.location).unwrap())
+            .collect();
+        assert_eq!(contents, [b"stored".to_vec(), b"deflated content".to_vec()])
+    }
+
+    #[test]
+    fn zip_sizes_are_bounded_by_the_archive()
+    {
+        let mut bytes = zip_archive(&[("a.png", 0, b"stored", b"stored")]);
+        let end = bytes.len() - 22;
+        bytes[end + 12..end + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(zip_entries(&mut io::Cursor::new(&bytes)).is_err());
+        let mut bytes = zip_archive(&[("a.png", 0, b"stored", b"stored")]);
+        let directory = bytes.len() - 22 - 46 - 5;
+        bytes[directory + 20..directory + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(zip_entries(&mut io::Cursor::new(&bytes)).is_err())
+    }
+
+    #[test]
+    fn tar_entries_are_listed_and_read()
+    {
+        let long_name = format!("{}/c.png", "nested".repeat(20));
+        let mut bytes = vec![];
+        tar_file(&mut bytes, tar_header("a.png", b'0', 3, ""), b"one");
+        tar_file(&mut bytes, tar_header("pictures", b'5', 0, ""), b"");
+        tar_file(&mut bytes, tar_header("b.png", b'0', 3, "pictures"), b"two");
+        let link = tar_header("././@LongLink", b'L', long_name.len(), "");
+        tar_file(&mut bytes, link, long_name.as_bytes());
+        tar_file(&mut bytes, tar_header("c.png", b'0', 5, ""), b"three");
+        bytes.extend([0; 1024]);
+        let mut reader = io::Cursor::new(bytes);
+        let entries = tar_entries(&mut reader).unwrap();
+        assert_eq!(names(&entries), ["a.png", "pictures/b.png", long_name.as_str()]);
+        let contents: Vec<_> = entries.iter()
+            .map(|entry| read_data(&mut reader, entry.location).unwrap())
+            .collect();
+        assert_eq!(contents, [b"one".to_vec(), b"two".to_vec(), b"three".to_vec()])
+    }
+
+    #[test]
+    fn tar_sizes_are_bounded_by_the_archive()
+    {
+        let mut bytes = vec![];
+        tar_file(&mut bytes, tar_header("a.png", b'0', 1 << 30, ""), b"");
+        assert!(tar_entries(&mut io::Cursor::new(&bytes)).is_err());
+        let mut bytes = vec![];
+        tar_file(&mut bytes, tar_header("././@LongLink", b'L', 1 << 30, ""), b"");
+        assert!(tar_entries(&mut io::Cursor::new(&bytes)).is_err())
+    }
+
+    #[test]
+    fn tar_numbers_are_octal_or_base_256()
+    {
+        assert_eq!(tar_number(b"0000644\0").unwrap(), 0o644);
+        assert_eq!(tar_number(b"  1750 \0").unwrap(), 0o1750);
+        assert_eq!(tar_number(b"\0\0\0\0").unwrap(), 0);
+        assert_eq!(tar_number(&[0x80, 0, 0, 1, 0]).unwrap(), 256);
+        assert!(tar_number(b"0009\0").is_err())
+    }
+
+    #[test]
+    fn entry_names_stay_inside_the_archive()
+    {
+        assert_eq!(entry_name(Path::new("a/b/c.png")).as_deref(), Some("a/b/c.png"));
+        assert_eq!(entry_name(Path::new("../c.png")), None);
+        assert_eq!(entry_name(Path::new("/c.png")), None);
+        assert_eq!(entry_name(Path::new("a/./c.png")).as_deref(), Some("a/c.png"))
+    }
+}
@@ -0,0 +1,37 @@
+
+use {std::path::*, ochra::thumbnails::*};
+
+// ------------------------------------------------------------
+
+const USAGE: &str = "Usage: ochra-thumbnailer [-s size] input output";
+
+fn thumbnail(arguments: &[String]) -> anyhow::Result<()>
+{
+    let (size, input, output) = match arguments
+    {
+        [flag, size, input, output] if flag == "-s" => (size.parse()?, input, output),
+        [input, output] => (ThumbnailSize::Normal.pixels(), input, output),
+        _ => anyhow::bail!(USAGE)
+    };
+    let input = match input.starts_with("file://")
+    {
+        true => filepath_from_uri(input)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported URI {input}"))?,
+        false => PathBuf::from(input)
+    };
+    let (thumbnail, dimensions) = generate_thumbnail(&input, size)?;
+    save_thumbnail(Path::new(output), &input, &thumbnail, dimensions)?;
+    Ok(())
+}
+
+// ------------------------------------------------------------
+
+fn main()
+{
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(error) = thumbnail(&arguments)
+    {
+        eprintln!("{error}");
+        std::process::exit(1)
+    }
+}
@@ -0,0 +1,735 @@
+
+use
+{
+    std::io::{self, Read, Seek, SeekFrom}
+};
+
+// ------------------------------------------------------------
+
+pub fn invalid_data<E>(error: E) -> io::Error
+where E: Into<Box<dyn std::error::Error + Send + Sync>>
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn read_sized<R: Read>(reader: &mut R, size: u64) -> io::Result<Vec<u8>>
+{
+    let mut bytes = vec![];
+    reader.take(size).read_to_end(&mut bytes)?;
+    match bytes.len() as u64 == size
+    {
+        true => Ok(bytes),
+        false => Err(invalid_data("Truncated chunk"))
+    }
+}
+
+fn read_u32_le<R: Read>(reader: &mut R) -> io::Result<u32>
+{
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u32_be(bytes: &[u8]) -> io::Result<u32>
+{
+    bytes.get(..4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid_data("Truncated box header"))
+}
+
+// ------------------------------------------------------------
+
+pub fn tiff_icc_profile<R>(reader: &mut R) -> io::Result<Option<Vec<u8>>>
+where R: Read + Seek
+{
+    const ICC_PROFILE: u16 = 34675;
+    tiff::decoder::Decoder::new(reader)
+        .and_then
+        (
+            |mut decoder| decoder.find_tag_unsigned_vec::<u8>
+            (
+                tiff::tags::Tag::Unknown(ICC_PROFILE)
+            )
+        ).map_err(invalid_data)
+}
+
+// ------------------------------------------------------------
+
+pub fn webp_icc_profile<R>(reader: &mut R) -> io::Result<Option<Vec<u8>>>
+where R: Read + Seek
+{
+    let mut fourcc = [0; 4];
+    reader.read_exact(&mut fourcc)?;
+    let riff_size = read_u32_le(reader)? as u64;
+    let mut form = [0; 4];
+    reader.read_exact(&mut form)?;
+    if &fourcc != b"RIFF" || &form != b"WEBP"
+    {
+        return Err(invalid_data("Not a WebP container"))
+    }
+    let mut consumed = 4;
+    while consumed + 8 <= riff_size
+    {
+        reader.read_exact(&mut fourcc)?;
+        let size = read_u32_le(reader)? as u64;
+        let padded = size + (size & 1);
+        if consumed + 8 + size > riff_size
+        {
+            return Err(invalid_data("WebP chunk exceeds container"))
+        }
+        if &fourcc == b"ICCP"
+        {
+            return read_sized(reader, size).map(Some)
+        }
+        reader.seek(SeekFrom::Current(padded as i64))?;
+        consumed += 8 + padded
+    }
+    Ok(None)
+}
+
+// ------------------------------------------------------------
+
+pub fn bmp_icc_profile<R>(reader: &mut R) -> io::Result<Option<Vec<u8>>>
+where R: Read + Seek
+{
+    const FILE_HEADER_SIZE: u64 = 14;
+    const V5_HEADER_SIZE: u32 = 124;
+    const PROFILE_EMBEDDED: u32 = u32::from_be_bytes(*b"MBED");
+    let start = reader.stream_position()?;
+    reader.seek(SeekFrom::Start(start + FILE_HEADER_SIZE))?;
+    let mut header = vec![0; V5_HEADER_SIZE as usize];
+    reader.read_exact(&mut header[..4])?;
+    if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) < V5_HEADER_SIZE
+    {
+        return Ok(None)
+    }
+    reader.read_exact(&mut header[4..])?;
+    let field = |offset: usize| u32::from_le_bytes
+    (
+        [header[offset], header[offset + 1], header[offset + 2], header[offset + 3]]
+    );
+    if field(56) != PROFILE_EMBEDDED
+    {
+        return Ok(None)
+    }
+    let (offset, size) = (field(112) as u64, field(116) as u64);
+    let length = reader.seek(SeekFrom::End(0))?;
+    if start + FILE_HEADER_SIZE + offset + size > length
+    {
+        return Err(invalid_data("BMP profile exceeds file"))
+    }
+    reader.seek(SeekFrom::Start(start + FILE_HEADER_SIZE + offset))?;
+    read_sized(reader, size).map(Some)
+}
+
+// ------------------------------------------------------------
+
+pub fn gif_play_count(bytes: &[u8]) -> Option<u32>
+{
+    const HEADER_SIZE: usize = 13;
+    let flags = *bytes.get(10)?;
+    let mut cursor = HEADER_SIZE + match flags & 0x80
+    {
+        0 => 0,
+        _ => 3 << ((flags & 0x07) + 1)
+    };
+    while let Some(&introducer) = bytes.get(cursor)
+    {
+        if introducer != 0x21
+        {
+            break
+        }
+        let label = *bytes.get(cursor + 1)?;
+        cursor += 2;
+        if label == 0xFF && bytes.get(cursor..cursor + 12)? == b"\x0BNETSCAPE2.0"
+        {
+            let block = bytes.get(cursor + 12..cursor + 16)?;
+            return match u16::from_le_bytes([block[2], block[3]])
+            {
+                0 => None,
+                loops => Some(loops as u32 + 1)
+            }
+        }
+        while let Some(&size) = bytes.get(cursor)
+        {
+            cursor += size as usize + 1;
+            if size == 0
+            {
+                break
+            }
+        }
+    }
+    Some(1)
+}
+
+// ------------------------------------------------------------
+
+pub fn apng_play_count(bytes: &[u8]) -> Option<u32>
+{
+    const SIGNATURE_SIZE: usize = 8;
+    let mut cursor = SIGNATURE_SIZE;
+    while let Some(header) = bytes.get(cursor..cursor + 8)
+    {
+        let size = read_u32_be(header).ok()? as usize;
+        match &header[4..]
+        {
+            b"acTL" => return match read_u32_be(bytes.get(cursor + 12..)?).ok()?
+            {
+                0 => None,
+                plays => Some(plays)
+            },
+            b"IDAT" => break,
+            _ => cursor += size + 12
+        }
+    }
+    None
+}
+
+// ------------------------------------------------------------
+
+pub fn webp_play_count(bytes: &[u8]) -> Option<u32>
+{
+    const HEADER_SIZE: usize = 12;
+    let mut cursor = HEADER_SIZE;
+    while let Some(header) = bytes.get(cursor..cursor + 8)
+    {
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        if &header[..4] == b"ANIM"
+        {
+            let loops = bytes.get(cursor + 12..cursor + 14)?;
+            return match u16::from_le_bytes([loops[0], loops[1]])
+            {
+                0 => None,
+                loops => Some(loops as u32)
+            }
+        }
+        cursor += 8 + size + (size & 1)
+    }
+    None
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug)]
+pub enum SubImage
+{
+    Tiff
+    {
+        directory: u64
+    },
+    Ico
+    {
+        entry: usize
+    },
+    Dds
+    {
+        width: u32,
+        height: u32,
+        offset: usize,
+        size: usize
+    }
+}
+
+impl SubImage
+{
+    pub fn extract(&self, bytes: &[u8]) -> io::Result<Vec<u8>>
+    {
+        match *self
+        {
+            Self::Tiff{directory} => extract_tiff_directory(bytes, directory),
+            Self::Ico{entry} => extract_ico_entry(bytes, entry),
+            Self::Dds{width, height, offset, size}
+                => extract_dds_surface(bytes, [width, height], offset, size)
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+pub fn exif_fields<R>(reader: &mut R, tags: &[exif::Tag]) -> Vec<(exif::Tag, exif::Value)>
+where R: Read + Seek
+{
+    const EXIF_HEAD: u64 = 1 << 20;
+    let Ok(start) = reader.stream_position() else {return vec![]};
+    match tiff_exif_fields(reader, tags)
+    {
+        Ok(Some(fields)) => return fields,
+        Ok(None) => {}
+        Err(..) => return vec![]
+    }
+    let mut head = vec![];
+    if reader.seek(SeekFrom::Start(start)).is_err()
+        || reader.take(EXIF_HEAD).read_to_end(&mut head).is_err()
+    {
+        return vec![]
+    }
+    exif::Reader::new()
+        .read_from_container(&mut io::Cursor::new(head))
+        .map_or_else
+        (
+            |_| vec![],
+            |exif| tags.iter()
+                .filter_map
+                (
+                    |&tag| exif.get_field(tag, exif::In::PRIMARY)
+                        .map(|field| (tag, field.value.clone()))
+                )
+                .collect()
+        )
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+struct TiffLayout
+{
+    big_endian: bool,
+    big_tiff: bool
+}
+
+impl TiffLayout
+{
+    fn read(bytes: &[u8]) -> io::Result<Self>
+    {
+        let big_endian = match bytes.get(..2)
+        {
+            Some(b"II") => false,
+            Some(b"MM") => true,
+            _ => return Err(invalid_data("Not a TIFF container"))
+        };
+        let this = Self{big_endian, big_tiff: false};
+        let big_tiff = match this.uint(bytes, 2, 2)?
+        {
+            42 => false,
+            43 => true,
+            _ => return Err(invalid_data("Not a TIFF container"))
+        };
+        Ok(Self{big_endian, big_tiff})
+    }
+
+    fn uint(&self, bytes: &[u8], offset: u64, width: usize) -> io::Result<u64>
+    {
+        let field = usize::try_from(offset).ok()
+            .and_then(|offset| bytes.get(offset..offset.checked_add(width)?))
+            .ok_or_else(|| invalid_data("Truncated TIFF directory"))?;
+        let fold = |value: u64, byte: &u8| value << 8 | *byte as u64;
+        Ok
+        (
+            match self.big_endian
+            {
+                true => field.iter().fold(0, fold),
+                false => field.iter().rev().fold(0, fold)
+            }
+        )
+    }
+
+    fn put_uint(&self, bytes: &mut [u8], offset: u64, width: usize, value: u64)
+    {
+        let offset = offset as usize;
+        for index in 0..width
+        {
+            let shift = match self.big_endian
+            {
+                true => 8 * (width - 1 - index),
+                false => 8 * index
+            };
+            bytes[offset + index] = (value >> shift) as u8
+        }
+    }
+
+    fn offset_width(&self) -> usize
+    {
+        match self.big_tiff
+        {
+            true => 8,
+            false => 4
+        }
+    }
+
+    fn first_directory_position(&self) -> u64
+    {
+        match self.big_tiff
+        {
+            true => 8,
+            false => 4
+        }
+    }
+
+    fn next_directory_position(&self, bytes: &[u8], directory: u64) -> io::Result<u64>
+    {
+        let (count_width, entry_size) = match self.big_tiff
+        {
+            true => (8, 20),
+            false => (2, 12)
+        };
+        let count = self.uint(bytes, directory, count_width)?;
+        count.checked_mul(entry_size)
+            .and_then(|size| size.checked_add(directory + count_width as u64))
+            .ok_or_else(|| invalid_data("Malformed TIFF directory"))
+    }
+}
+
+pub fn tiff_is_paged<R>(reader: &mut R) -> io::Result<bool>
+where R: Read + Seek
+{
+    let start = reader.stream_position()?;
+    let mut header = [0; 16];
+    reader.read_exact(&mut header)?;
+    let layout = TiffLayout::read(&header)?;
+    let width = layout.offset_width();
+    let directory = layout.uint(&header, layout.first_directory_position(), width)?;
+    let mut count = [0; 8];
+    reader.seek(SeekFrom::Start(start + directory))?;
+    reader.read_exact(&mut count)?;
+    let next = layout.next_directory_position(&count, 0)?;
+    let mut next_directory = [0; 8];
+    reader.seek(SeekFrom::Start(start + directory + next))?;
+    reader.read_exact(&mut next_directory[..width])?;
+    Ok(layout.uint(&next_directory, 0, width)? != 0)
+}
+
+pub fn tiff_sub_images(bytes: &[u8]) -> io::Result<Vec<SubImage>>
+{
+    let layout = TiffLayout::read(bytes)?;
+    let width = layout.offset_width();
+    let mut directories = vec![];
+    let mut directory = layout.uint(bytes, layout.first_directory_position(), width)?;
+    while directory != 0 && !directories.contains(&directory)
+    {
+        directories.push(directory);
+        let next = layout.next_directory_position(bytes, directory)?;
+        directory = layout.uint(bytes, next, width)?
+    }
+    Ok
+    (
+        directories.into_iter()
+            .map(|directory| SubImage::Tiff{directory})
+            .collect()
+    )
+}
+
+fn extract_tiff_directory(bytes: &[u8], directory: u64) -> io::Result<Vec<u8>>
+{
+    let layout = TiffLayout::read(bytes)?;
+    let width = layout.offset_width();
+    let next = layout.next_directory_position(bytes, directory)?;
+    layout.uint(bytes, next, width)?;
+    let mut extracted = bytes.to_vec();
+    layout.put_uint(&mut extracted, layout.first_directory_position(), width, directory);
+    layout.put_uint(&mut extracted, next, width, 0);
+    Ok(extracted)
+}
+
+fn tiff_exif_fields<R>(reader: &mut R, tags: &[exif::Tag])
+    -> io::Result<Option<Vec<(exif::Tag, exif::Value)>>>
+where R: Read + Seek
+{
+    const EXIF_DIRECTORY: u16 = 0x8769;
+    const MAX_ENTRIES: u64 = 1024;
+    const MAX_ASCII: u64 = 256;
+    let start = reader.stream_position()?;
+    let mut header = [0; 16];
+    reader.read_exact(&mut header)?;
+    let layout = match TiffLayout::read(&header)
+    {
+        Ok(layout) => layout,
+        Err(..) => return Ok(None)
+    };
+    let width = layout.offset_width();
+    let (count_width, entry_size) = match layout.big_tiff
+    {
+        true => (8, 20),
+        false => (2, 12)
+    };
+    let mut directories = vec![layout.uint(&header, layout.first_directory_position(), width)?];
+    let mut visited = vec![];
+    let mut fields = vec![];
+    while let Some(directory) = directories.pop()
+    {
+        if visited.contains(&directory)
+        {
+            continue
+        }
+        visited.push(directory);
+        let mut count = [0; 8];
+        reader.seek(SeekFrom::Start(start.saturating_add(directory)))?;
+        reader.read_exact(&mut count[..count_width])?;
+        let count = layout.uint(&count, 0, count_width)?.min(MAX_ENTRIES);
+        let entries = read_sized(reader, count * entry_size as u64)?;
+        for entry in entries.chunks_exact(entry_size)
+        {
+            let number = layout.uint(entry, 0, 2)? as u16;
+            let kind = layout.uint(entry, 2, 2)?;
+            let count = layout.uint(entry, 4, width)?;
+            let value = 4 + width as u64;
+            if number == EXIF_DIRECTORY
+            {
+                directories.push(layout.uint(entry, value, width)?);
+                continue
+            }
+            let Some(&tag) = tags.iter().find(|tag| tag.number() == number) else {continue};
+            let field = match (kind, count)
+            {
+                (1, 1) => exif::Value::Byte(vec![layout.uint(entry, value, 1)? as u8]),
+                (3, 1) => exif::Value::Short(vec![layout.uint(entry, value, 2)? as u16]),
+                (4, 1) => exif::Value::Long(vec![layout.uint(entry, value, 4)? as u32]),
+                (2, count) =>
+                {
+                    let mut ascii = match count <= width as u64
+                    {
+                        true => entry[value as usize..][..count as usize].to_vec(),
+                        false =>
+                        {
+                            let position = reader.stream_position()?;
+                            let offset = layout.uint(entry, value, width)?;
+                            reader.seek(SeekFrom::Start(start.saturating_add(offset)))?;
+                            let ascii = read_sized(reader, count.min(MAX_ASCII))?;
+                            reader.seek(SeekFrom::Start(position))?;
+                            ascii
+                        }
+                    };
+                    ascii.truncate(ascii.iter().position(|&byte| byte == 0).unwrap_or(ascii.len()));
+                    exif::Value::Ascii(vec![ascii])
+                }
+                _ => continue
+            };
+            fields.push((tag, field))
+        }
+    }
+    Ok(Some(fields))
+}
+
+// ------------------------------------------------------------
+
+const ICO_HEADER_SIZE: usize = 6;
+const ICO_ENTRY_SIZE: usize = 16;
+
+pub fn ico_is_paged<R>(reader: &mut R) -> io::Result<bool>
+where R: Read + Seek
+{
+    let mut header = [0; ICO_HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+    Ok(u16::from_le_bytes([header[4], header[5]]) > 1)
+}
+
+pub fn ico_sub_images(bytes: &[u8]) -> io::Result<Vec<SubImage>>
+{
+    let count = bytes.get(4..6)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+        .ok_or_else(|| invalid_data("Truncated ICO header"))?;
+    let mut entries = (0..count)
+        .map
+        (
+            |entry|
+            {
+                let start = ICO_HEADER_SIZE + entry * ICO_ENTRY_SIZE;
+                let header = bytes.get(start..start + ICO_ENTRY_SIZE)
+                    .ok_or_else(|| invalid_data("Truncated ICO directory"))?;
+                let side = |byte: u8| match byte
+                {
+                    0 => 256,
+                    side => side as u32
+                };
+                let depth = u16::from_le_bytes([header[6], header[7]]);
+                Ok((depth, side(header[0]) * side(header[1]), entry))
+            }
+        )
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|&(depth, area, _)| std::cmp::Reverse((depth, area)));
+    Ok
+    (
+        entries.into_iter()
+            .map(|(_, _, entry)| SubImage::Ico{entry})
+            .collect()
+    )
+}
+
+fn extract_ico_entry(bytes: &[u8], entry: usize) -> io::Result<Vec<u8>>
+{
+    let start = ICO_HEADER_SIZE + entry * ICO_ENTRY_SIZE;
+    let header = bytes.get(..4)
+        .zip(bytes.get(start..start + ICO_ENTRY_SIZE))
+        .ok_or_else(|| invalid_data("Truncated ICO directory"))?;
+    let size = read_u32_le(&mut &header.1[8..12])? as usize;
+    let offset = read_u32_le(&mut &header.1[12..16])? as usize;
+    let image = offset.checked_add(size)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or_else(|| invalid_data("Truncated ICO image"))?;
+    let mut extracted = Vec::with_capacity(ICO_HEADER_SIZE + ICO_ENTRY_SIZE + size);
+    extracted.extend_from_slice(header.0);
+    extracted.extend_from_slice(&1u16.to_le_bytes());
+    extracted.extend_from_slice(&header.1[..12]);
+    extracted.extend_from_slice(&((ICO_HEADER_SIZE + ICO_ENTRY_SIZE) as u32).to_le_bytes());
+    extracted.extend_from_slice(image);
+    Ok(extracted)
+}
+
+// ------------------------------------------------------------
+
+const DDS_HEADER_SIZE: usize = 128;
+const DDS_DX10_HEADER_SIZE: usize = 20;
+const DDS_MIPMAP_COUNT_FLAG: u32 = 0x20000;
+const DDS_CUBEMAP_FLAG: u32 = 0x200;
+const DDS_CUBEMAP_FACE_FLAGS: u32 = 0xFC00;
+const DDS_DX10_CUBEMAP_FLAG: u32 = 0x4;
+
+fn dds_field(bytes: &[u8], offset: usize) -> io::Result<u32>
+{
+    bytes.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid_data("Truncated DDS header"))
+}
+
+fn dds_is_dx10(bytes: &[u8]) -> bool
+{
+    bytes.get(84..88) == Some(b"DX10")
+}
+
+pub fn dds_is_paged<R>(reader: &mut R) -> io::Result<bool>
+where R: Read + Seek
+{
+    let mut header = vec![];
+    reader.take((DDS_HEADER_SIZE + DDS_DX10_HEADER_SIZE) as u64)
+        .read_to_end(&mut header)?;
+    let mipmapped = dds_field(&header, 8)? & DDS_MIPMAP_COUNT_FLAG != 0 &&
+        dds_field(&header, 28)? > 1;
+    let layered = match dds_is_dx10(&header)
+    {
+        true => dds_field(&header, DDS_HEADER_SIZE + 12)? > 1 ||
+            dds_field(&header, DDS_HEADER_SIZE + 8)? & DDS_DX10_CUBEMAP_FLAG != 0,
+        false => dds_field(&header, 112)? & DDS_CUBEMAP_FLAG != 0
+    };
+    Ok(mipmapped || layered)
+}
+
+pub fn dds_sub_images(bytes: &[u8]) -> io::Result<Vec<SubImage>>
+{
+    if bytes.get(..4) != Some(b"DDS ")
+    {
+        return Err(invalid_data("Not a DDS container"))
+    }
+    let flags = dds_field(bytes, 8)?;
+    let (height, width) = (dds_field(bytes, 12)?, dds_field(bytes, 16)?);
+    let mipmaps = match flags & DDS_MIPMAP_COUNT_FLAG
+    {
+        0 => 1,
+        _ => dds_field(bytes, 28)?.max(1)
+    };
+    let (block_size, layers, start) = match dds_is_dx10(bytes)
+    {
+        true =>
+        {
+            let block_size = match dds_field(bytes, DDS_HEADER_SIZE)?
+            {
+                70..=72 => 8,
+                73..=78 => 16,
+                _ => return Ok(vec![])
+            };
+            let faces = match dds_field(bytes, DDS_HEADER_SIZE + 8)? & DDS_DX10_CUBEMAP_FLAG
+            {
+                0 => 1,
+                _ => 6
+            };
+            let layers = dds_field(bytes, DDS_HEADER_SIZE + 12)?.max(1) * faces;
+            (block_size, layers, DDS_HEADER_SIZE + DDS_DX10_HEADER_SIZE)
+        }
+        false =>
+        {
+            let block_size = match bytes.get(84..88)
+            {
+                Some(b"DXT1") => 8,
+                Some(b"DXT3" | b"DXT5") => 16,
+                _ => return Ok(vec![])
+            };
+            let caps2 = dds_field(bytes, 112)?;
+            let layers = match caps2 & DDS_CUBEMAP_FLAG
+            {
+                0 => 1,
+                _ => (caps2 & DDS_CUBEMAP_FACE_FLAGS).count_ones().max(1)
+            };
+            (block_size, layers, DDS_HEADER_SIZE)
+        }
+    };
+    let mut surfaces = vec![];
+    let mut offset = start;
+    for _ in 0..layers
+    {
+        for level in 0..mipmaps.min(32)
+        {
+            let (width, height) = ((width >> level).max(1), (height >> level).max(1));
+            let size = (width as usize).div_ceil(4).max(1) *
+                (height as usize).div_ceil(4).max(1) * block_size;
+            if offset + size > bytes.len()
+            {
+                return Ok(surfaces)
+            }
+            if width % 4 == 0 && height % 4 == 0
+            {
+                surfaces.push(SubImage::Dds{width, height, offset, size})
+            }
+            offset += size
+        }
+    }
+    Ok(surfaces)
+}
+
+fn extract_dds_surface
+(
+    bytes: &[u8],
+    [width, height]: [u32; 2],
+    offset: usize,
+    size: usize
+) -> io::Result<Vec<u8>>
+{
+    let header_size = match dds_is_dx10(bytes)
+    {
+        true => DDS_HEADER_SIZE + DDS_DX10_HEADER_SIZE,
+        false => DDS_HEADER_SIZE
+    };
+    let (header, surface) = bytes.get(..header_size)
+        .zip(bytes.get(offset..offset + size))
+        .ok_or_else(|| invalid_data("Truncated DDS surface"))?;
+    let mut extracted = [header, surface].concat();
+    let mut put = |offset: usize, value: u32|
+        extracted[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    put(8, dds_field(bytes, 8)? & !DDS_MIPMAP_COUNT_FLAG);
+    put(12, height);
+    put(16, width);
+    put(28, 1);
+    put(112, 0);
+    if header_size > DDS_HEADER_SIZE
+    {
+        put(DDS_HEADER_SIZE + 8, 0);
+        put(DDS_HEADER_SIZE + 12, 1)
+    }
+    Ok(extracted)
+}
+
+// ------------------------------------------------------------
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn tiff_exif_fields_are_read_from_directories()
+    {
+        use tiff::{encoder::*, tags::Tag};
+        let mut bytes = io::Cursor::new(vec![]);
+        let mut encoder = TiffEncoder::new(&mut bytes).unwrap();
+        let mut image = encoder.new_image::<colortype::Gray8>(2, 2).unwrap();
+        image.encoder().write_tag(Tag::Unknown(0x0112), 6u16).unwrap();
+        image.encoder().write_tag(Tag::Unknown(0x0132), "2020:01:02 03:04:05").unwrap();
+        image.write_data(&[0; 4]).unwrap();
+        bytes.set_position(0);
+        let fields = exif_fields(&mut bytes, &[exif::Tag::Orientation, exif::Tag::DateTime]);
+        assert_eq!(fields[0].0, exif::Tag::Orientation);
+        assert_eq!(fields[0].1.get_uint(0), Some(6));
+        assert_eq!(fields[1].0, exif::Tag::DateTime);
+        assert!
+        (
+            matches!(&fields[1].1, exif::Value::Ascii(values) if values == &[b"2020:01:02 03:04:05"])
+        )
+    }
+}
@@ -0,0 +1,635 @@
+
+use
+{
+    std::{io::{self, Read, Seek}, path::Path, sync::{Arc, OnceLock}},
+    image::{ImageFormat, ImageDecoder, codecs::*},
+    super::
+    {
+        containers,
+        picture::*,
+        progressive::{self, PartialSink}
+    }
+};
+
+// ------------------------------------------------------------
+
+pub trait PictureRead: io::BufRead + io::Seek {}
+
+impl<R> PictureRead for R where R: io::BufRead + io::Seek {}
+
+pub trait PictureDecoder: Send + Sync
+{
+    fn extensions(&self) -> Vec<&'static str>;
+    fn matches(&self, magic: &[u8]) -> bool;
+    fn layout(&self, reader: &mut dyn PictureRead) -> PictureResult<(PictureDimensions, u64)>;
+    fn decode(&self, reader: Box<dyn PictureRead>, partial: &mut PartialSink) -> PictureResult<Picture>;
+
+    fn preview(&self, _reader: Box<dyn PictureRead>) -> PictureResult<Option<StillPicture>>
+    {
+        Ok(None)
+    }
+}
+
+// ------------------------------------------------------------
+
+static REGISTRY: OnceLock<DecoderRegistry> = OnceLock::new();
+
+pub struct DecoderRegistry
+{
+    decoders: Vec<Box<dyn PictureDecoder>>
+}
+
+impl DecoderRegistry
+{
+    pub const MAGIC_SIZE: usize = 32;
+
+    pub fn empty() -> Self
+    {
+        Self{decoders: vec![]}
+    }
+
+    pub fn register<D>(&mut self, decoder: D)
+    where D: PictureDecoder + 'static
+    {
+        self.decoders.push(Box::new(decoder))
+    }
+
+    pub fn install(self) -> Result<(), Self>
+    {
+        REGISTRY.set(self)
+    }
+
+    pub fn global() -> &'static Self
+    {
+        REGISTRY.get_or_init(Self::default)
+    }
+
+    pub fn extensions(&self) -> Vec<&'static str>
+    {
+        let mut extensions = vec![];
+        for extension in self.decoders.iter().flat_map(|decoder| decoder.extensions())
+        {
+            if !extensions.contains(&extension)
+            {
+                extensions.push(extension)
+            }
+        }
+        extensions
+    }
+
+    pub fn find(&self, magic: &[u8], extension: Option<&str>) -> Option<&dyn PictureDecoder>
+    {
+        let extension = extension.map(str::to_lowercase);
+        self.decoders.iter().rev()
+            .find(|decoder| decoder.matches(magic))
+            .or_else
+            (
+                || self.decoders.iter().rev().find
+                (
+                    |decoder| decoder.extensions()
+                        .iter()
+                        .any(|e| Some(*e) == extension.as_deref())
+                )
+            )
+            .map(Box::as_ref)
+    }
+
+    pub fn select<R>(&self, reader: &mut R, filepath: &Path) -> PictureResult<&dyn PictureDecoder>
+    where R: io::BufRead + io::Seek
+    {
+        let position = reader.stream_position().map_err(PictureError::IO)?;
+        let mut magic = vec![];
+        reader.by_ref()
+            .take(Self::MAGIC_SIZE as u64)
+            .read_to_end(&mut magic)
+            .and_then(|_| reader.seek(io::SeekFrom::Start(position)))
+            .map_err(PictureError::IO)?;
+        let extension = filepath.extension().and_then(|extension| extension.to_str());
+        self.find(&magic, extension)
+            .ok_or(PictureError::UnsupportedImageFormat)
+    }
+}
+
+impl Default for DecoderRegistry
+{
+    fn default() -> Self
+    {
+        let mut this = Self::empty();
+        this.register(Png);
+        this.register(Jpeg);
+        this.register(Gif);
+        this.register(WebP);
+        this.register(Raster::new(ImageFormat::Pnm));
+        this.register(Tiff);
+        this.register(Raster::new(ImageFormat::Tga));
+        this.register(Raster{pages: Some(Pages::DDS), ..Raster::new(ImageFormat::Dds)});
+        this.register(Raster{icc: Some(containers::bmp_icc_profile), ..Raster::new(ImageFormat::Bmp)});
+        this.register(Raster{pages: Some(Pages::ICO), ..Raster::new(ImageFormat::Ico)});
+        this.register(HighDynamicRange(ImageFormat::Hdr));
+        this.register(HighDynamicRange(ImageFormat::OpenExr));
+        this.register(Raster::new(ImageFormat::Farbfeld));
+        this
+    }
+}
+
+// ------------------------------------------------------------
+
+pub type IccReader = fn(&mut Box<dyn PictureRead>) -> io::Result<Option<Vec<u8>>>;
+pub type SubImages = fn(&[u8]) -> io::Result<Vec<containers::SubImage>>;
+pub type PageProbe = fn(&mut Box<dyn PictureRead>) -> io::Result<bool>;
+
+#[derive(Clone, Copy)]
+pub struct Pages
+{
+    pub probe: PageProbe,
+    pub sub_images: SubImages
+}
+
+impl Pages
+{
+    pub const TIFF: Self = Self{probe: containers::tiff_is_paged, sub_images: containers::tiff_sub_images};
+    pub const ICO: Self = Self{probe: containers::ico_is_paged, sub_images: containers::ico_sub_images};
+    pub const DDS: Self = Self{probe: containers::dds_is_paged, sub_images: containers::dds_sub_images};
+}
+
+fn guessed(magic: &[u8], format: ImageFormat) -> bool
+{
+    image::guess_format(magic).ok() == Some(format)
+}
+
+fn decoder_layout<'a, D>(decoder: D) -> (PictureDimensions, u64)
+where D: ImageDecoder<'a>
+{
+    let (width, height) = decoder.dimensions();
+    ([width, height], decoder.color_type().bytes_per_pixel() as u64)
+}
+
+fn image_layout(reader: &mut dyn PictureRead, format: ImageFormat)
+    -> PictureResult<(PictureDimensions, u64)>
+{
+    match format
+    {
+        ImageFormat::Png => png::PngDecoder::new(reader).map(decoder_layout),
+        ImageFormat::Jpeg => jpeg::JpegDecoder::new(reader).map(decoder_layout),
+        ImageFormat::Gif => gif::GifDecoder::new(reader).map(decoder_layout),
+        ImageFormat::WebP => webp::WebPDecoder::new(reader).map(decoder_layout),
+        ImageFormat::Tiff => tiff::TiffDecoder::new(reader).map(decoder_layout),
+        ImageFormat::Bmp => bmp::BmpDecoder::new(reader).map(decoder_layout),
+        ImageFormat::Tga => tga::TgaDecoder::new(reader).map(decoder_layout),
+        ImageFormat::Pnm => pnm::PnmDecoder::new(reader).map(decoder_layout),
+        ImageFormat::Farbfeld => farbfeld::FarbfeldDecoder::new(reader).map(decoder_layout),
+        ImageFormat::Hdr => hdr::HdrDecoder::new(reader)
+            .map(|decoder| decoder.metadata())
+            .map(|metadata| ([metadata.width, metadata.height], 3 * 4)),
+        ImageFormat::OpenExr => openexr::OpenExrDecoder::new(reader).map(decoder_layout),
+        _ => image::io::Reader::with_format(reader, format)
+            .into_dimensions()
+            .map(|(width, height)| ([width, height], DecodeBudget::MAX_BYTES_PER_PIXEL))
+    }.map_err(PictureError::ImageError)
+}
+
+fn decode_image(reader: Box<dyn PictureRead>, format: ImageFormat, budget: &DecodeBudget)
+    -> PictureResult<image::DynamicImage>
+{
+    let mut reader = image::io::Reader::with_format(reader, format);
+    reader.limits(budget.limits());
+    reader.decode().map_err(PictureError::ImageError)
+}
+
+fn decode_radiance(reader: Box<dyn PictureRead>) -> PictureResult<image::DynamicImage>
+{
+    let decoder = hdr::HdrDecoder::new(reader).map_err(PictureError::ImageError)?;
+    let metadata = decoder.metadata();
+    let pixels = decoder.read_image_hdr().map_err(PictureError::ImageError)?;
+    image::Rgb32FImage::from_raw
+    (
+        metadata.width,
+        metadata.height,
+        pixels.into_iter().flat_map(|pixel| pixel.0).collect()
+    )
+        .map(image::DynamicImage::ImageRgb32F)
+        .ok_or(PictureError::UnsupportedPixelFormat)
+}
+
+fn rewind(reader: &mut Box<dyn PictureRead>, position: u64) -> PictureResult<()>
+{
+    reader.seek(io::SeekFrom::Start(position))
+        .map(|_| ())
+        .map_err(PictureError::IO)
+}
+
+struct Prelude
+{
+    position: u64,
+    budget: DecodeBudget,
+    orientation: Orientation
+}
+
+impl Prelude
+{
+    fn read(reader: &mut Box<dyn PictureRead>, format: ImageFormat) -> PictureResult<Self>
+    {
+        let position = reader.stream_position().map_err(PictureError::IO)?;
+        let budget = DecodeBudget::from_env();
+        let (dimensions, bytes_per_pixel) = image_layout(reader.as_mut(), format)?;
+        budget.check(dimensions, bytes_per_pixel)?;
+        rewind(reader, position)?;
+        let orientation = Orientation::read(reader);
+        rewind(reader, position)?;
+        Ok(Self{position, budget, orientation})
+    }
+
+    fn still(&self, icc: lcms2::Profile, dynamic_image: image::DynamicImage)
+        -> PictureResult<StillPicture>
+    {
+        StillPicture::try_from((icc, self.orientation.apply(dynamic_image)))
+    }
+
+    fn paged<D>
+    (
+        &self,
+        decoder: &D,
+        reader: &mut Box<dyn PictureRead>,
+        Pages{probe, sub_images}: Pages
+    ) -> PictureResult<Option<Picture>>
+    where D: PictureDecoder + Clone + 'static
+    {
+        let paged = probe(reader).unwrap_or(false);
+        rewind(reader, self.position)?;
+        if !paged
+        {
+            return Ok(None)
+        }
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).map_err(PictureError::IO)?;
+        rewind(reader, self.position)?;
+        let pages = sub_images(&bytes).unwrap_or_default();
+        if pages.len() <= 1
+        {
+            return Ok(None)
+        }
+        let decoder = decoder.clone();
+        let decode = move |page: Vec<u8>| decoder.decode
+        (
+            Box::new(io::Cursor::new(page)),
+            &mut PartialSink::none()
+        );
+        Ok(Some(Picture::Paged(PagedPicture::new(bytes.into(), pages, Arc::new(decode)))))
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct Png;
+
+impl PictureDecoder for Png
+{
+    fn extensions(&self) -> Vec<&'static str>
+    {
+        ImageFormat::Png.extensions_str().to_vec()
+    }
+
+    fn matches(&self, magic: &[u8]) -> bool
+    {
+        guessed(magic, ImageFormat::Png)
+    }
+
+    fn layout(&self, reader: &mut dyn PictureRead) -> PictureResult<(PictureDimensions, u64)>
+    {
+        image_layout(reader, ImageFormat::Png)
+    }
+
+    fn decode(&self, mut reader: Box<dyn PictureRead>, partial: &mut PartialSink) -> PictureResult<Picture>
+    {
+        let prelude = Prelude::read(&mut reader, ImageFormat::Png)?;
+        let mut decoder = png::PngDecoder::new(&mut reader)
+            .map_err(PictureError::ImageError)?;
+        let icc = embedded_icc(decoder.icc_profile())?;
+        prelude.budget.check_decoder(&decoder)?;
+        match decoder.is_apng()
+        {
+            false =>
+            {
+                drop(decoder);
+                rewind(&mut reader, prelude.position)?;
+                let dynamic_image = progressive::decode_png
+                (
+                    &mut reader,
+                    &prelude.budget,
+                    partial,
+                    |d| prelude.still(lcms2::Profile::new_icc(&icc.icc()?)?, d)
+                )?;
+                prelude.still(icc, dynamic_image).map(Picture::Still)
+            }
+            true =>
+            {
+                let source = AnimationSource::new
+                (
+                    reader,
+                    prelude.position,
+                    |r| png::PngDecoder::new(r).map(|d| d.apng()),
+                    containers::apng_play_count
+                )?;
+                FramesPlayer::try_from((icc, source)).map(Picture::Motion)
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct Jpeg;
+
+impl PictureDecoder for Jpeg
+{
+    fn extensions(&self) -> Vec<&'static str>
+    {
+        ImageFormat::Jpeg.extensions_str().to_vec()
+    }
+
+    fn matches(&self, magic: &[u8]) -> bool
+    {
+        guessed(magic, ImageFormat::Jpeg)
+    }
+
+    fn layout(&self, reader: &mut dyn PictureRead) -> PictureResult<(PictureDimensions, u64)>
+    {
+        image_layout(reader, ImageFormat::Jpeg)
+    }
+
+    fn decode(&self, mut reader: Box<dyn PictureRead>, _: &mut PartialSink) -> PictureResult<Picture>
+    {
+        let prelude = Prelude::read(&mut reader, ImageFormat::Jpeg)?;
+        let mut decoder = jpeg::JpegDecoder::new(reader)
+            .map_err(PictureError::ImageError)?;
+        let icc = embedded_icc(decoder.icc_profile())?;
+        prelude.budget.check_decoder(&decoder)?;
+        image::DynamicImage::from_decoder(decoder)
+            .map_err(PictureError::ImageError)
+            .and_then(|d| prelude.still(icc, d))
+            .map(Picture::Still)
+    }
+
+    fn preview(&self, mut reader: Box<dyn PictureRead>) -> PictureResult<Option<StillPicture>>
+    {
+        const PREVIEW_THRESHOLD: u64 = 16_000_000;
+        const PREVIEW_SIZE: f32 = 2048.0;
+        let position = reader.stream_position().map_err(PictureError::IO)?;
+        let orientation = Orientation::read(&mut reader);
+        rewind(&mut reader, position)?;
+        let mut decoder = jpeg::JpegDecoder::new(reader)
+            .map_err(PictureError::ImageError)?;
+        let (width, height) = decoder.dimensions();
+        if (width as u64) * (height as u64) < PREVIEW_THRESHOLD
+        {
+            return Ok(None)
+        }
+        let factor = width.max(height) as f32 / PREVIEW_SIZE;
+        decoder.scale
+        (
+            (width as f32 / factor).ceil() as u16,
+            (height as f32 / factor).ceil() as u16
+        ).map_err(PictureError::ImageError)?;
+        let icc = embedded_icc(decoder.icc_profile())?;
+        image::DynamicImage::from_decoder(decoder)
+            .map_err(PictureError::ImageError)
+            .and_then(|d| StillPicture::try_from((icc, orientation.apply(d))))
+            .map(Some)
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct Gif;
+
+impl PictureDecoder for Gif
+{
+    fn extensions(&self) -> Vec<&'static str>
+    {
+        ImageFormat::Gif.extensions_str().to_vec()
+    }
+
+    fn matches(&self, magic: &[u8]) -> bool
+    {
+        guessed(magic, ImageFormat::Gif)
+    }
+
+    fn layout(&self, reader: &mut dyn PictureRead) -> PictureResult<(PictureDimensions, u64)>
+    {
+        image_layout(reader, ImageFormat::Gif)
+    }
+
+    fn decode(&self, mut reader: Box<dyn PictureRead>, _: &mut PartialSink) -> PictureResult<Picture>
+    {
+        let prelude = Prelude::read(&mut reader, ImageFormat::Gif)?;
+        let source = AnimationSource::new
+        (
+            reader,
+            prelude.position,
+            gif::GifDecoder::new,
+            containers::gif_play_count
+        )?;
+        FramesPlayer::try_from((lcms2::Profile::new_srgb(), source)).map(Picture::Motion)
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct WebP;
+
+impl PictureDecoder for WebP
+{
+    fn extensions(&self) -> Vec<&'static str>
+    {
+        ImageFormat::WebP.extensions_str().to_vec()
+    }
+
+    fn matches(&self, magic: &[u8]) -> bool
+    {
+        guessed(magic, ImageFormat::WebP)
+    }
+
+    fn layout(&self, reader: &mut dyn PictureRead) -> PictureResult<(PictureDimensions, u64)>
+    {
+        image_layout(reader, ImageFormat::WebP)
+    }
+
+    fn decode(&self, mut reader: Box<dyn PictureRead>, _: &mut PartialSink) -> PictureResult<Picture>
+    {
+        let prelude = Prelude::read(&mut reader, ImageFormat::WebP)?;
+        let embedded = containers::webp_icc_profile(&mut reader).map_err(PictureError::IO)?;
+        rewind(&mut reader, prelude.position)?;
+        let decoder = webp::WebPDecoder::new(&mut reader)
+            .map_err(PictureError::ImageError)?;
+        let icc = embedded_icc(embedded)?;
+        prelude.budget.check_decoder(&decoder)?;
+        match decoder.has_animation()
+        {
+            false => image::DynamicImage::from_decoder(decoder)
+                .map_err(PictureError::ImageError)
+                .and_then(|d| prelude.still(icc, d))
+                .map(Picture::Still),
+            true =>
+            {
+                let source = AnimationSource::new
+                (
+                    reader,
+                    prelude.position,
+                    webp::WebPDecoder::new,
+                    containers::webp_play_count
+                )?;
+                FramesPlayer::try_from((icc, source)).map(Picture::Motion)
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct Tiff;
+
+impl PictureDecoder for Tiff
+{
+    fn extensions(&self) -> Vec<&'static str>
+    {
+        ImageFormat::Tiff.extensions_str().to_vec()
+    }
+
+    fn matches(&self, magic: &[u8]) -> bool
+    {
+        guessed(magic, ImageFormat::Tiff)
+    }
+
+    fn layout(&self, reader: &mut dyn PictureRead) -> PictureResult<(PictureDimensions, u64)>
+    {
+        image_layout(reader, ImageFormat::Tiff)
+    }
+
+    fn decode(&self, mut reader: Box<dyn PictureRead>, partial: &mut PartialSink) -> PictureResult<Picture>
+    {
+        let prelude = Prelude::read(&mut reader, ImageFormat::Tiff)?;
+        if let Some(paged) = prelude.paged(self, &mut reader, Pages::TIFF)?
+        {
+            return Ok(paged)
+        }
+        let embedded = containers::tiff_icc_profile(&mut reader).map_err(PictureError::IO)?;
+        rewind(&mut reader, prelude.position)?;
+        let icc = embedded_icc(embedded)?;
+        let dynamic_image = match progressive::decode_tiff
+        (
+            &mut reader,
+            &prelude.budget,
+            partial,
+            |d| prelude.still(lcms2::Profile::new_icc(&icc.icc()?)?, d)
+        )?
+        {
+            Some(dynamic_image) => dynamic_image,
+            None =>
+            {
+                rewind(&mut reader, prelude.position)?;
+                decode_image(reader, ImageFormat::Tiff, &prelude.budget)?
+            }
+        };
+        prelude.still(icc, dynamic_image).map(Picture::Still)
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct HighDynamicRange(pub ImageFormat);
+
+impl PictureDecoder for HighDynamicRange
+{
+    fn extensions(&self) -> Vec<&'static str>
+    {
+        self.0.extensions_str().to_vec()
+    }
+
+    fn matches(&self, magic: &[u8]) -> bool
+    {
+        guessed(magic, self.0)
+    }
+
+    fn layout(&self, reader: &mut dyn PictureRead) -> PictureResult<(PictureDimensions, u64)>
+    {
+        image_layout(reader, self.0)
+    }
+
+    fn decode(&self, mut reader: Box<dyn PictureRead>, _: &mut PartialSink) -> PictureResult<Picture>
+    {
+        let prelude = Prelude::read(&mut reader, self.0)?;
+        let dynamic_image = match self.0
+        {
+            ImageFormat::Hdr => decode_radiance(reader)?,
+            format => decode_image(reader, format, &prelude.budget)?
+        };
+        let mut still = prelude.still(lcms2::Profile::new_srgb(), dynamic_image)?;
+        if let PixelData::Float(..) = still.pixel_data
+        {
+            still.gamma = 1.0 / 2.2
+        }
+        Ok(Picture::Still(still))
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct Raster
+{
+    pub format: ImageFormat,
+    pub icc: Option<IccReader>,
+    pub pages: Option<Pages>
+}
+
+impl Raster
+{
+    pub fn new(format: ImageFormat) -> Self
+    {
+        Self{format, icc: None, pages: None}
+    }
+}
+
+impl PictureDecoder for Raster
+{
+    fn extensions(&self) -> Vec<&'static str>
+    {
+        self.format.extensions_str().to_vec()
+    }
+
+    fn matches(&self, magic: &[u8]) -> bool
+    {
+        guessed(magic, self.format)
+    }
+
+    fn layout(&self, reader: &mut dyn PictureRead) -> PictureResult<(PictureDimensions, u64)>
+    {
+        image_layout(reader, self.format)
+    }
+
+    fn decode(&self, mut reader: Box<dyn PictureRead>, _: &mut PartialSink) -> PictureResult<Picture>
+    {
+        let prelude = Prelude::read(&mut reader, self.format)?;
+        if let Some(pages) = self.pages
+        {
+            if let Some(paged) = prelude.paged(self, &mut reader, pages)?
+            {
+                return Ok(paged)
+            }
+        }
+        let embedded = match self.icc
+        {
+            Some(read) => read(&mut reader).map_err(PictureError::IO)?,
+            None => None
+        };
+        rewind(&mut reader, prelude.position)?;
+        let dynamic_image = decode_image(reader, self.format, &prelude.budget)?;
+        prelude.still(embedded_icc(embedded)?, dynamic_image).map(Picture::Still)
+    }
+}
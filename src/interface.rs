@@ -1,7 +1,7 @@
 
 use
 {
-    std::{fmt, time::*, ops::*},
+    std::{fmt, time::*, ops::*, rc::Rc},
     winit::{window::*, event::*, event_loop::*, dpi::*},
     super::
     {
@@ -77,7 +77,9 @@ impl RenderWindow
 struct InterfaceRenderer
 {
     main: RenderWindow,
-    stamp: RenderWindow
+    stamp: RenderWindow,
+    tone: Tone,
+    status: Option<PictureStatus>
 }
 
 impl InterfaceRenderer
@@ -99,7 +101,16 @@ impl InterfaceRenderer
         stamp.clear();
         spin(SPIN_TIME);
         stamp.set_visible(true);
-        Ok(Self{main, stamp})
+        Ok
+        (
+            Self
+            {
+                main,
+                stamp,
+                tone: Default::default(),
+                status: None
+            }
+        )
     }
 
     fn get_window_id(&self) -> WindowId
@@ -220,6 +231,31 @@ impl InterfaceRenderer
         self.draw()
     }
 
+    fn get_tone(&self) -> Tone
+    {
+        self.tone
+    }
+
+    fn set_tone(&mut self, tone: Tone)
+    {
+        self.tone = tone;
+        self.main.set_tone(&tone);
+        self.stamp.set_tone(&tone);
+        self.draw()
+    }
+
+    fn set_picture_status(&mut self, status: Option<PictureStatus>) -> bool
+    {
+        if self.status == status
+        {
+            return false
+        }
+        self.status = status;
+        self.main.set_picture_status(status);
+        self.stamp.set_picture_status(status);
+        true
+    }
+
     fn show_blank
     (
         &mut self,
@@ -232,11 +268,31 @@ impl InterfaceRenderer
             .map(|_| self.draw())
     }
 
-    fn show_picture(&mut self, mut still: StillPicture) -> PictureResult<()>
+    fn show_picture(&mut self, frame: PictureFrame) -> PictureResult<()>
     {
-        still.transform_to_icc(self.main.get_monitor_icc())?;
-        self.main.use_picture_mode(&still);
-        self.stamp.use_picture_mode(&still);
+        let (still, slot) = match frame
+        {
+            PictureFrame::Still(still) => (still, None),
+            PictureFrame::Decoded{index, still, resident}
+                => (Rc::new(still), resident.then_some(index)),
+            PictureFrame::Resident(index) =>
+            {
+                let available = self.main.use_resident_picture_mode(index) &&
+                    self.stamp.use_resident_picture_mode(index);
+                if !available
+                {
+                    return Err(PictureError::MissingResidentFrame(index))
+                }
+                self.draw();
+                return Ok(())
+            }
+        };
+        let still = still.to_icc(self.main.get_monitor_icc())?;
+        let stamp_pixels = self.stamp.get_screen_size()
+            .map_or(u32::MAX, |size| size.width.max(size.height));
+        let stamp = still.downscaled(stamp_pixels)?;
+        self.main.use_picture_mode(&still, slot);
+        self.stamp.use_picture_mode(stamp.as_ref().unwrap_or(&still), slot);
         Ok(self.draw())
     }
 
@@ -426,6 +482,21 @@ impl<I> InteractionMachine<I>
         self.interface.is_error()
     }
 
+    fn get_tone(&self) -> Tone
+    {
+        self.interface.get_tone()
+    }
+
+    fn set_tone(&mut self, tone: Tone)
+    {
+        self.interface.set_tone(tone)
+    }
+
+    fn set_picture_status(&mut self, status: Option<PictureStatus>) -> bool
+    {
+        self.interface.set_picture_status(status)
+    }
+
     fn draw(&mut self) -> ()
     {
         self.interface.draw()
@@ -469,10 +540,10 @@ impl InteractionMachine<DisabledInteraction>
         Ok(self.into())
     }
 
-    fn show_picture(mut self, still: StillPicture)
+    fn show_picture(mut self, frame: PictureFrame)
         -> anyhow::Result<InteractionMachine<NoInteraction>>
     {
-        match self.interface.show_picture(still)
+        match self.interface.show_picture(frame)
         {
             Ok(()) => Ok(self.into()),
             Err(error) => self.show_error(&error)
@@ -555,9 +626,9 @@ impl InteractionMachine<NoInteraction>
         self.interface.show_blank(dimensions)
     }
 
-    fn show_picture(&mut self, still: StillPicture) -> anyhow::Result<()>
+    fn show_picture(&mut self, frame: PictureFrame) -> anyhow::Result<()>
     {
-        self.interface.show_picture(still)
+        self.interface.show_picture(frame)
             .or_else(|e| self.show_error(&e))
     }
 
@@ -685,9 +756,9 @@ impl InteractionMachine<DragInteraction>
         self.interface.show_blank(dimensions)
     }
 
-    fn show_picture(&mut self, still: StillPicture) -> anyhow::Result<()>
+    fn show_picture(&mut self, frame: PictureFrame) -> anyhow::Result<()>
     {
-        self.interface.show_picture(still)
+        self.interface.show_picture(frame)
             .or_else(|e| self.show_error(&e))
     }
 
@@ -761,7 +832,7 @@ impl InteractionMachine<ZoomInteraction>
         this.interface.show_blank(dimensions).map(|_| this)
     }
 
-    fn show_picture(mut self, still: StillPicture) -> anyhow::Result
+    fn show_picture(mut self, frame: PictureFrame) -> anyhow::Result
     <
         Cases2
         <
@@ -770,7 +841,7 @@ impl InteractionMachine<ZoomInteraction>
         >
     >
     {
-        match self.interface.show_picture(still)
+        match self.interface.show_picture(frame)
         {
             Ok(()) => Ok(Cases2::A(self)),
             Err(error) => Ok(Cases2::B(self.show_error(&error)?))
@@ -1013,21 +1084,21 @@ impl InterfaceEnum
         }
     }
 
-    fn show_picture(mut self, still: StillPicture) -> anyhow::Result<Self>
+    fn show_picture(mut self, frame: PictureFrame) -> anyhow::Result<Self>
     {
         match self
         {
             Self::DisabledInteraction(interaction)
-                => interaction.show_picture(still)
+                => interaction.show_picture(frame)
                     .map(Into::into),
             Self::NoInteraction(ref mut interaction)
-                => interaction.show_picture(still)
+                => interaction.show_picture(frame)
                     .map(|_| self),
             Self::DragInteraction(ref mut interaction)
-                => interaction.show_picture(still)
+                => interaction.show_picture(frame)
                     .map(|_| self),
             Self::ZoomInteraction(interaction)
-                => interaction.show_picture(still).map
+                => interaction.show_picture(frame).map
             (
                 |cases| match cases
                 {
@@ -1073,6 +1144,51 @@ impl InterfaceEnum
         }
     }
 
+    fn get_tone(&self) -> Tone
+    {
+        match self
+        {
+            Self::DisabledInteraction(interaction)
+                => interaction.get_tone(),
+            Self::NoInteraction(interaction)
+                => interaction.get_tone(),
+            Self::DragInteraction(interaction)
+                => interaction.get_tone(),
+            Self::ZoomInteraction(interaction)
+                => interaction.get_tone()
+        }
+    }
+
+    fn set_tone(&mut self, tone: Tone)
+    {
+        match self
+        {
+            Self::DisabledInteraction(interaction)
+                => interaction.set_tone(tone),
+            Self::NoInteraction(interaction)
+                => interaction.set_tone(tone),
+            Self::DragInteraction(interaction)
+                => interaction.set_tone(tone),
+            Self::ZoomInteraction(interaction)
+                => interaction.set_tone(tone)
+        }
+    }
+
+    fn set_picture_status(&mut self, status: Option<PictureStatus>) -> bool
+    {
+        match self
+        {
+            Self::DisabledInteraction(interaction)
+                => interaction.set_picture_status(status),
+            Self::NoInteraction(interaction)
+                => interaction.set_picture_status(status),
+            Self::DragInteraction(interaction)
+                => interaction.set_picture_status(status),
+            Self::ZoomInteraction(interaction)
+                => interaction.set_picture_status(status)
+        }
+    }
+
     fn draw(&mut self) -> ()
     {
         match self
@@ -1125,9 +1241,9 @@ impl Interface
         self.0.show_blank(dimensions).map(Self)
     }
 
-    pub fn show_picture(self, still: StillPicture) -> anyhow::Result<Self>
+    pub fn show_picture(self, frame: PictureFrame) -> anyhow::Result<Self>
     {
-        self.0.show_picture(still).map(Self)
+        self.0.show_picture(frame).map(Self)
     }
 
     pub fn show_error<E>(self, error: &E) -> anyhow::Result<Self>
@@ -1141,6 +1257,21 @@ impl Interface
         self.0.is_error()
     }
 
+    pub fn get_tone(&self) -> Tone
+    {
+        self.0.get_tone()
+    }
+
+    pub fn set_tone(&mut self, tone: Tone)
+    {
+        self.0.set_tone(tone)
+    }
+
+    pub fn set_picture_status(&mut self, status: Option<PictureStatus>) -> bool
+    {
+        self.0.set_picture_status(status)
+    }
+
     pub fn draw(&mut self) -> ()
     {
         self.0.draw()
@@ -0,0 +1,17 @@
+
+pub mod utility;
+pub mod cases;
+pub mod ogl;
+pub mod painters;
+pub mod containers;
+pub mod archives;
+pub mod decoders;
+pub mod picture;
+pub mod progressive;
+pub mod thumbnails;
+pub mod loader;
+pub mod reader;
+pub mod renderer;
+pub mod interface;
+pub mod navigator;
+pub mod app;
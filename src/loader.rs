@@ -5,91 +5,217 @@ use
     {
         path::*,
         fmt,
-        sync::{Arc, Mutex, mpsc::*}
+        time::{Instant, SystemTime},
+        collections::VecDeque,
+        rc::Rc,
+        sync::{Arc, Mutex, atomic::*, mpsc::*}
     },
     super::
     {
         utility::*,
         picture::*,
+        progressive::PartialSink,
+        thumbnails::*
     }
 };
 
 // ------------------------------------------------------------
 
+type Decoding = Option<(PathBuf, Arc<AtomicBool>)>;
+
 struct ThreadedPictureDecoder
 {
-    send_to_thread_path: Sender<PathBuf>,
-    receive_on_main_path: Receiver<PathBuf>,
-    send_to_thread_continue: Sender<()>,
-    picture_result: Arc<Mutex<Option<PictureResult<Picture>>>>,
+    send_to_thread_paths: Sender<Vec<PathBuf>>,
+    requested: Arc<Mutex<Vec<PathBuf>>>,
+    decoding: Arc<Mutex<Decoding>>,
+    receive_on_main_picture: Receiver<(PathBuf, PictureResult<Picture>)>,
+    progress: Arc<Mutex<Option<(PathBuf, PictureProgress)>>>,
     current_path: Option<PathBuf>
 }
 
 impl ThreadedPictureDecoder
 {
-    fn new() -> Self 
-    {
-        let (send_to_thread_path, receive_on_thread_path)
-            : (Sender<PathBuf>, _) = channel();
-        let (send_to_main_path, receive_on_main_path)
-            : (Sender<PathBuf>, _) = channel();
-        let (send_to_thread_continue, receive_on_thread_continue)
-            : (Sender<()>, _) = channel();
-        let picture_result = Arc::new(Mutex::new(None));
-        let picture_result_thread = picture_result.clone();
+    fn new(waker: Waker) -> Self 
+    {
+        let (send_to_thread_paths, receive_on_thread_paths)
+            : (Sender<Vec<PathBuf>>, _) = channel();
+        let (send_to_main_picture, receive_on_main_picture)
+            : (Sender<(PathBuf, PictureResult<Picture>)>, _) = channel();
+        let progress = Arc::new(Mutex::new(None));
+        let progress_thread = progress.clone();
+        let requested = Arc::new(Mutex::new(Vec::<PathBuf>::new()));
+        let requested_thread = requested.clone();
+        let decoding = Arc::new(Mutex::new(None));
+        let decoding_thread = decoding.clone();
+        let (send_to_thumbnailer, receive_on_thumbnailer)
+            : (SyncSender<(PathBuf, StillPicture, PictureDimensions)>, _) = sync_channel(1);
         std::thread::spawn
         (
-            move || loop
+            move ||
             {
-                match receive_on_thread_path.try_iter().last()
+                let Some(thumbnails) = ThumbnailCache::new() else {return};
+                while let Ok((filepath, still, dimensions)) = receive_on_thumbnailer.recv()
                 {
-                    Some(filepath) =>
+                    let _ = thumbnails.store(&filepath, &still, dimensions, ThumbnailSize::XXLarge);
+                }
+            }
+        );
+        std::thread::spawn
+        (
+            move ||
+            {
+                let mut queue = VecDeque::new();
+                let mut fresh = false;
+                let thumbnails = ThumbnailCache::new();
+                loop
+                {
+                    let request = match queue.is_empty()
+                    {
+                        true => match receive_on_thread_paths.recv()
+                        {
+                            Ok(filepaths) => Some(filepaths),
+                            Err(..) => return
+                        },
+                        false => receive_on_thread_paths.try_iter().last()
+                    };
+                    if let Some(filepaths) = request
                     {
-                        *picture_result_thread.lock().unwrap()
-                            = Some(open_picture(&filepath));
-                        send_to_main_path.send(filepath).unwrap();
-                        receive_on_thread_continue.recv().unwrap()
+                        queue = filepaths.into();
+                        fresh = true
+                    }
+                    if let Some(filepath) = queue.pop_front()
+                    {
+                        let current = std::mem::take(&mut fresh);
+                        if current
+                        {
+                            if let Ok(dimensions) = read_dimensions(&filepath)
+                            {
+                                *progress_thread.lock().unwrap() = Some
+                                (
+                                    (filepath.clone(), PictureProgress::Loading(dimensions))
+                                );
+                                waker.wake()
+                            }
+                            let preview = open_preview(&filepath)
+                                .ok()
+                                .flatten()
+                                .or_else
+                                (
+                                    || thumbnails.as_ref()?
+                                        .find(&filepath, ThumbnailSize::XXLarge)
+                                );
+                            if let Some(still) = preview
+                            {
+                                *progress_thread.lock().unwrap() = Some
+                                (
+                                    (filepath.clone(), PictureProgress::Preview(still))
+                                );
+                                waker.wake()
+                            }
+                        }
+                        let cancelled = Arc::new(AtomicBool::new(false));
+                        *decoding_thread.lock().unwrap()
+                            = Some((filepath.clone(), cancelled.clone()));
+                        if !requested_thread.lock().unwrap().contains(&filepath)
+                        {
+                            continue
+                        }
+                        let partial = match current
+                        {
+                            true =>
+                            {
+                                let (progress, filepath, waker)
+                                    = (progress_thread.clone(), filepath.clone(), waker.clone());
+                                PartialSink::new
+                                (
+                                    move |still|
+                                    {
+                                        *progress.lock().unwrap() = Some
+                                        (
+                                            (filepath.clone(), PictureProgress::Partial(still))
+                                        );
+                                        waker.wake()
+                                    }
+                                )
+                            }
+                            false => PartialSink::none()
+                        };
+                        let picture = open_picture(&filepath, cancelled, partial);
+                        if let (true, Some(thumbnails), Ok(Picture::Still(still)))
+                            = (current, &thumbnails, &picture)
+                        {
+                            if !thumbnails.contains(&filepath, ThumbnailSize::XXLarge)
+                            {
+                                let copy = still.downscaled(2 * ThumbnailSize::XXLarge.pixels())
+                                    .and_then(|copy| copy.map_or_else(|| still.try_clone(), Ok));
+                                if let Ok(copy) = copy
+                                {
+                                    let _ = send_to_thumbnailer.try_send
+                                    (
+                                        (filepath.clone(), copy, still.resolution)
+                                    );
+                                }
+                            }
+                        }
+                        if !requested_thread.lock().unwrap().contains(&filepath)
+                        {
+                            continue
+                        }
+                        if send_to_main_picture.send((filepath, picture)).is_err()
+                        {
+                            return
+                        }
+                        waker.wake()
                     }
-                    None => {}
                 }
             }
         );
         Self
         {
-            send_to_thread_path,
-            receive_on_main_path,
-            send_to_thread_continue,
-            picture_result,
+            send_to_thread_paths,
+            requested,
+            decoding,
+            receive_on_main_picture,
+            progress,
             current_path: None
         }
     }
 
-    fn set_filepath<P: AsRef<Path>>(&mut self, path: P) -> ()
+    fn set_filepaths<P: AsRef<Path>>(&mut self, path: P, filepaths: Vec<PathBuf>)
     {
-        let path = path.as_ref().to_owned();
-        self.send_to_thread_path.send(path.clone())
+        *self.requested.lock().unwrap() = filepaths.clone();
+        if let Some((filepath, cancelled)) = self.decoding.lock().unwrap().as_ref()
+        {
+            if !filepaths.contains(filepath)
+            {
+                cancelled.store(true, Ordering::Relaxed)
+            }
+        }
+        self.send_to_thread_paths.send(filepaths)
             .map_err(|e| show_error_box(&e, true))
             .unwrap();
-        self.current_path = Some(path)
+        *self.progress.lock().unwrap() = None;
+        self.current_path = Some(path.as_ref().to_owned())
     }
 
-    fn try_fetch_picture(&self) -> Option<PictureResult<Picture>>
+    fn try_fetch_progress(&self) -> Option<PictureLoadResult>
     {
         let path = self.current_path.as_ref()?;
-        match self.receive_on_main_path.try_recv()
+        let mut progress = self.progress.lock().unwrap();
+        match progress.as_ref()
         {
-            Ok(filepath) =>
-            {
-                let result = filepath.eq(path).then
-                (
-                    || self.picture_result
-                        .lock().unwrap()
-                        .take().unwrap()
-                );
-                self.send_to_thread_continue
-                    .send(()).unwrap();
-                result
-            }
+            Some((filepath, _)) if filepath == path => progress
+                .take()
+                .map(|(_, progress)| progress.into()),
+            _ => None
+        }
+    }
+
+    fn try_fetch_picture(&self) -> Option<(PathBuf, PictureResult<Picture>)>
+    {
+        match self.receive_on_main_picture.try_recv()
+        {
+            Ok(result) => Some(result),
             Err(TryRecvError::Empty) => None,
             Err(error @ TryRecvError::Disconnected) =>
             {
@@ -102,22 +228,172 @@ impl ThreadedPictureDecoder
 
 // ------------------------------------------------------------
 
+struct CachedPicture
+{
+    filepath: PathBuf,
+    modified: Option<SystemTime>,
+    size: usize,
+    picture: PictureResult<FrameStreamer>
+}
+
+struct PictureCache
+{
+    entries: VecDeque<CachedPicture>,
+    size: usize
+}
+
+impl PictureCache
+{
+    const BUDGET: usize = 1 << 30;
+
+    fn new() -> Self
+    {
+        Self{entries: VecDeque::new(), size: 0}
+    }
+
+    fn modified(filepath: &Path) -> Option<SystemTime>
+    {
+        std::fs::metadata(filepath)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    fn insert(&mut self, filepath: PathBuf, picture: PictureResult<FrameStreamer>)
+    {
+        self.remove(&filepath);
+        let modified = Self::modified(&filepath);
+        let size = picture.as_ref().map_or(0, FrameStreamer::byte_len);
+        self.size += size;
+        self.entries.push_back(CachedPicture{filepath, modified, size, picture});
+        while self.size > Self::BUDGET && self.entries.len() > 1
+        {
+            let evicted = self.entries.pop_front().unwrap();
+            self.size -= evicted.size
+        }
+    }
+
+    fn remove(&mut self, filepath: &Path) -> Option<CachedPicture>
+    {
+        let index = self.entries.iter()
+            .position(|entry| entry.filepath == filepath)?;
+        let entry = self.entries.remove(index)?;
+        self.size -= entry.size;
+        Some(entry)
+    }
+
+    fn contains(&self, filepath: &Path) -> bool
+    {
+        let modified = Self::modified(filepath);
+        self.entries.iter()
+            .any(|entry| entry.filepath == filepath && entry.modified == modified)
+    }
+
+    fn take(&mut self, filepath: &Path) -> Option<PictureResult<FrameStreamer>>
+    {
+        self.remove(filepath)
+            .filter(|entry| entry.modified == Self::modified(filepath))
+            .map(|entry| entry.picture)
+    }
+}
+
+// ------------------------------------------------------------
+
 enum FrameStreamer
 {
-    Still(Option<StillPicture>),
-    Motion(FramesPlayer)
+    Still
+    {
+        still: Rc<StillPicture>,
+        shown: bool
+    },
+    Motion(FramesPlayer),
+    Paged(PagedPicture)
 }
 
 impl FrameStreamer
 {
-    fn next(&mut self) -> Option<PictureResult<StillPicture>>
+    fn next(&mut self) -> Option<PictureResult<PictureFrame>>
     {
         match self
         {
-            Self::Still(still) => still.take()
-                .map(|s| Ok(s)),
-            Self::Motion(player) => player.next()
-                .map(|s| s.clone())
+            Self::Still{still, shown} => match std::mem::replace(shown, true)
+            {
+                true => None,
+                false => Some(Ok(PictureFrame::Still(still.clone())))
+            },
+            Self::Motion(player) => player.next_frame(),
+            Self::Paged(paged) => paged.next_page()
+                .map(|r| r.map(PictureFrame::Still))
+        }
+    }
+}
+
+impl FrameStreamer
+{
+    fn control(&mut self, control: PlaybackControl)
+    {
+        if let Self::Motion(player) = self
+        {
+            player.control(control)
+        }
+    }
+
+    fn turn_page(&mut self, offset: isize)
+    {
+        if let Self::Paged(paged) = self
+        {
+            paged.turn(offset)
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant>
+    {
+        match self
+        {
+            Self::Still{..} => None,
+            Self::Motion(player) => player.deadline(),
+            Self::Paged(..) => None
+        }
+    }
+
+    fn set_waker(&self, waker: Waker)
+    {
+        match self
+        {
+            Self::Still{..} => (),
+            Self::Motion(player) => player.set_waker(waker),
+            Self::Paged(paged) => paged.set_waker(waker)
+        }
+    }
+
+    fn status(&self) -> Option<PictureStatus>
+    {
+        match self
+        {
+            Self::Still{..} => None,
+            Self::Motion(player) => Some(PictureStatus::Playback(player.status())),
+            Self::Paged(paged) => Some(PictureStatus::Page(paged.status()))
+        }
+    }
+
+    fn byte_len(&self) -> usize
+    {
+        match self
+        {
+            Self::Still{still, ..} => still.pixel_data.byte_len(),
+            Self::Motion(player) => player.byte_len(),
+            Self::Paged(paged) => paged.byte_len()
+        }
+    }
+}
+
+impl FrameStreamer
+{
+    fn into_cached(self) -> Option<Self>
+    {
+        match self
+        {
+            Self::Still{still, ..} => Some(Self::Still{still, shown: false}),
+            _ => None
         }
     }
 }
@@ -129,9 +405,11 @@ impl From<Picture> for FrameStreamer
         match picture
         {
             Picture::Still(still)
-                => Self::Still(Some(still)),
+                => Self::Still{still: Rc::new(still), shown: false},
             Picture::Motion(motion)
-                => Self::Motion(motion)    
+                => Self::Motion(motion),
+            Picture::Paged(paged)
+                => Self::Paged(paged)
         }
     }
 }
@@ -140,9 +418,8 @@ impl From<Picture> for FrameStreamer
 
 enum PictureLoadState
 {
-    PictureError(Option<PictureError>),
     Loading(Option<PictureDimensions>),
-    Loaded(FrameStreamer)
+    Loaded(Box<FrameStreamer>)
 }
 
 impl fmt::Debug for PictureLoadState
@@ -151,11 +428,6 @@ impl fmt::Debug for PictureLoadState
     {
         match self
         {
-            Self::PictureError(error) => write!
-            (
-                formatter,
-                "PictureLoadState::PictureError({error:?})"
-            ),
             Self::Loading(dimensions) => write!
             (
                 formatter, 
@@ -170,23 +442,33 @@ impl fmt::Debug for PictureLoadState
     }
 }
 
-impl From<PictureResult<PictureDimensions>> for PictureLoadState
+impl From<FrameStreamer> for PictureLoadState
 {
-    fn from(result: PictureResult<PictureDimensions>) -> Self
+    fn from(streamer: FrameStreamer) -> Self
     {
-        match result.map(|r| Some(r)).map_err(|e| Some(e))
-        {
-            Ok(dimensions) => Self::Loading(dimensions),
-            Err(error) => Self::PictureError(error)
-        }    
+        Self::Loaded(Box::new(streamer))
     }
 }
 
-impl From<Picture> for PictureLoadState
+// ------------------------------------------------------------
+
+enum PictureProgress
 {
-    fn from(picture: Picture) -> Self
+    Loading(PictureDimensions),
+    Preview(StillPicture),
+    Partial(StillPicture)
+}
+
+impl From<PictureProgress> for PictureLoadResult
+{
+    fn from(progress: PictureProgress) -> Self
     {
-        Self::Loaded(picture.into())
+        match progress
+        {
+            PictureProgress::Loading(dimensions) => Self::Loading(dimensions),
+            PictureProgress::Preview(still) => Self::Preview(still),
+            PictureProgress::Partial(still) => Self::Partial(still)
+        }
     }
 }
 
@@ -196,7 +478,9 @@ pub enum PictureLoadResult
 {
     PictureError(PictureError),
     Loading(PictureDimensions),
-    Loaded(StillPicture)
+    Preview(StillPicture),
+    Partial(StillPicture),
+    Loaded(PictureFrame)
 }
 
 impl fmt::Debug for PictureLoadResult
@@ -215,6 +499,16 @@ impl fmt::Debug for PictureLoadResult
                 formatter, 
                 "PictureLoadResult::Loading({dimensions:?})"
             ),
+            Self::Preview(..) => write!
+            (
+                formatter,
+                "PictureLoadResult::Preview"
+            ),
+            Self::Partial(..) => write!
+            (
+                formatter,
+                "PictureLoadResult::Partial"
+            ),
             Self::Loaded(..) => write!
             (
                 formatter,
@@ -240,22 +534,22 @@ impl From<PictureDimensions> for PictureLoadResult
     }
 }
 
-impl From<StillPicture> for PictureLoadResult
+impl From<PictureFrame> for PictureLoadResult
 {
-    fn from(still: StillPicture) -> Self
+    fn from(frame: PictureFrame) -> Self
     {
-        Self::Loaded(still)
+        Self::Loaded(frame)
     }
 }
 
-impl From<PictureResult<StillPicture>> for PictureLoadResult
+impl From<PictureResult<PictureFrame>> for PictureLoadResult
 {
-    fn from(result: PictureResult<StillPicture>) -> Self
+    fn from(result: PictureResult<PictureFrame>) -> Self
     {
         match result
         {
-            Ok(still) => PictureLoadResult
-                ::Loaded(still),
+            Ok(frame) => PictureLoadResult
+                ::Loaded(frame),
             Err(error) => PictureLoadResult
                 ::PictureError(error)
         }
@@ -266,25 +560,81 @@ impl From<PictureResult<StillPicture>> for PictureLoadResult
 
 pub struct PictureLoader
 {
+    waker: Waker,
     decoder: ThreadedPictureDecoder,
+    cache: PictureCache,
+    filepath: Option<PathBuf>,
     picture: Option<PictureLoadState>
 }
 
 impl PictureLoader
 {
-    pub fn new() -> Self
+    pub fn new(waker: Waker) -> Self
     {
         Self
         {
-            decoder: ThreadedPictureDecoder::new(),
+            decoder: ThreadedPictureDecoder::new(waker.clone()),
+            waker,
+            cache: PictureCache::new(),
+            filepath: None,
             picture: None
         }
     }
 
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> ()
+    pub fn load<P: AsRef<Path>>(&mut self, path: P, neighbors: &[PathBuf])
+    {
+        let path = path.as_ref().to_owned();
+        if let (Some(filepath), Some(PictureLoadState::Loaded(streamer)))
+            = (self.filepath.take(), self.picture.take())
+        {
+            if let Some(streamer) = streamer.into_cached()
+                .filter(|_| filepath != path)
+            {
+                self.cache.insert(filepath, Ok(streamer))
+            }
+        }
+        let filepaths = std::iter::once(&path)
+            .chain(neighbors)
+            .filter(|filepath| !self.cache.contains(filepath))
+            .cloned()
+            .collect();
+        self.decoder.set_filepaths(&path, filepaths);
+        self.picture = Some(PictureLoadState::Loading(None));
+        self.filepath = Some(path)
+    }
+
+    pub fn control_playback(&mut self, control: PlaybackControl)
+    {
+        if let Some(PictureLoadState::Loaded(streamer)) = &mut self.picture
+        {
+            streamer.control(control)
+        }
+    }
+
+    pub fn turn_page(&mut self, offset: isize)
+    {
+        if let Some(PictureLoadState::Loaded(streamer)) = &mut self.picture
+        {
+            streamer.turn_page(offset)
+        }
+    }
+
+    pub fn picture_status(&self) -> Option<PictureStatus>
+    {
+        match &self.picture
+        {
+            Some(PictureLoadState::Loaded(streamer)) => streamer.status(),
+            _ => None
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Instant>
     {
-        self.decoder.set_filepath(&path);
-        self.picture = Some(read_dimensions(&path).into())
+        match &self.picture
+        {
+            Some(PictureLoadState::Loaded(streamer)) => streamer.deadline(),
+            _ => None
+        }
     }
 }
 
@@ -293,24 +643,28 @@ impl Iterator for PictureLoader
     type Item = PictureLoadResult;
     fn next(&mut self) -> Option<Self::Item>
     {
+        while let Some((filepath, picture)) = self.decoder.try_fetch_picture()
+        {
+            self.cache.insert(filepath, picture.map(FrameStreamer::from))
+        }
         match &mut self.picture
         {
             Some(state) => match state
             {
-                PictureLoadState::PictureError(error)
-                    => error.take().map(Into::into),
                 PictureLoadState::Loading(dimensions)
                     => match dimensions.take()
                 {
                     Some(dimensions) => Some(dimensions.into()),
-                    None => match self.decoder.try_fetch_picture()?
+                    None => match self.cache.take(self.filepath.as_ref()?)
                     {
-                        Ok(picture) =>
+                        Some(Ok(streamer)) =>
                         {
-                            self.picture = Some(picture.into());
+                            streamer.set_waker(self.waker.clone());
+                            self.picture = Some(streamer.into());
                             self.next()
                         }
-                        Err(error) => Some(error.into())
+                        Some(Err(error)) => Some(error.into()),
+                        None => self.decoder.try_fetch_progress()
                     }
                 }
                 PictureLoadState::Loaded(streamer) => 
@@ -3,24 +3,16 @@
 
 // ------------------------------------------------------------
 
-mod utility;
-mod cases;
-mod ogl;
-mod painters;
-mod picture;
-mod loader;
-mod reader;
-mod renderer;
-mod interface;
-mod navigator;
-mod app;
+use ochra::{app, navigator, utility};
 
 // ------------------------------------------------------------
 
 fn main() -> !
 {
-    let path = std::env::args().nth(1).unwrap_or_default();
-    let (mut app, event_loop) = app::App::new(path)
+    let source = navigator::FilepathsSource::from_args(std::env::args().skip(1))
+        .map_err(|e| utility::show_error_box(&e, true))
+        .unwrap();
+    let (mut app, event_loop) = app::App::new(source)
         .map_err(|e| utility::show_error_box(&e, true))
         .unwrap();
     event_loop.run
@@ -29,7 +21,7 @@ fn main() -> !
         {
             winit::event::Event::WindowEvent{event: window_event, window_id}
                 => app.process_window_event(window_event, window_id, control_flow),
-            winit::event::Event::MainEventsCleared => app.refresh(),
+            winit::event::Event::MainEventsCleared => app.refresh(control_flow),
             winit::event::Event::RedrawRequested(..) => Ok(app.draw()),
             _ => Ok(())
         }.map_err(|e| utility::show_error_box(&e, true))
@@ -1,8 +1,10 @@
 
-use::
+use
 {
     std::
     {
+        cmp,
+        collections::{HashMap, HashSet},
         fmt,
         fs,
         io,
@@ -11,6 +13,7 @@ use::
         path::*,
         time::*
     },
+    super::{utility::Waker, archives, containers},
     notify::
     {
         Watcher as _,
@@ -67,11 +70,32 @@ pub struct Watcher
 
 impl Watcher
 {
-    pub fn watch<P: AsRef<Path>>(path: P) -> result::Result<Self, notify::Error>
+    pub fn watch<I>(targets: I, waker: Waker) -> result::Result<Self, notify::Error>
+    where I: IntoIterator<Item = (PathBuf, bool)>
     {
-        let (sender, receiver) = mpsc::channel();
+        let (sender, events) = mpsc::channel();
         let mut watcher = notify::watcher(sender, Duration::from_millis(250))?;
-        watcher.watch(path, notify::RecursiveMode::NonRecursive)?;
+        for (path, recursive) in targets
+        {
+            let mode = match recursive
+            {
+                true => notify::RecursiveMode::Recursive,
+                false => notify::RecursiveMode::NonRecursive
+            };
+            watcher.watch(path, mode)?
+        }
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn
+        (
+            move || for event in events
+            {
+                if sender.send(event).is_err()
+                {
+                    break
+                }
+                waker.wake()
+            }
+        );
         Ok(Self{watcher, receiver})
     }
 
@@ -87,6 +111,7 @@ enum FileType
 {
     Directory,
     File,
+    Archive,
     SymbolicLink,
     Unknown
 }
@@ -96,7 +121,8 @@ impl <P: AsRef<Path>> From<P> for FileType
     fn from(path: P) -> Self
     {
         let path = path.as_ref();
-        if path.is_file() { Self::File }
+        if path.is_file() && archives::is_archive(path) { Self::Archive }
+        else if path.is_file() { Self::File }
         else if path.is_dir() { Self::Directory } 
         else if path.is_symlink() { Self::SymbolicLink }
         else { Self::Unknown }
@@ -110,7 +136,7 @@ impl FileType
         let path = path.as_ref().to_owned();
         let directory_path = match Self::from(&path)
         {
-            Self::Directory => path,
+            Self::Directory | Self::Archive => path,
             Self::File => path.parent().unwrap().to_owned(),
             _ => return Err(NavigatorError::InvalidPath(path))
         };
@@ -120,19 +146,384 @@ impl FileType
 
 // ------------------------------------------------------------
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SortKey
+{
+    Name,
+    Modified,
+    Size,
+    DateTaken
+}
+
+impl SortKey
+{
+    pub fn next(&self) -> Self
+    {
+        match self
+        {
+            Self::Name => Self::Modified,
+            Self::Modified => Self::Size,
+            Self::Size => Self::DateTaken,
+            Self::DateTaken => Self::Name
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SortOrder
+{
+    pub key: SortKey,
+    pub reverse: bool
+}
+
+impl Default for SortOrder
+{
+    fn default() -> Self
+    {
+        Self{key: SortKey::Name, reverse: false}
+    }
+}
+
+impl SortOrder
+{
+    pub fn next(&self) -> Self
+    {
+        match self.reverse
+        {
+            false => Self{reverse: true, ..*self},
+            true => Self{key: self.key.next(), reverse: false}
+        }
+    }
+}
+
+fn split_digits(text: &str) -> (&str, &str)
+{
+    let end = text.find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text.split_at(end)
+}
+
+fn natural_str_order(left: &str, right: &str) -> cmp::Ordering
+{
+    let (mut left, mut right) = (left, right);
+    loop
+    {
+        let (x, y) = match (left.chars().next(), right.chars().next())
+        {
+            (None, None) => return cmp::Ordering::Equal,
+            (None, Some(..)) => return cmp::Ordering::Less,
+            (Some(..), None) => return cmp::Ordering::Greater,
+            (Some(x), Some(y)) => (x, y)
+        };
+        let order = match x.is_ascii_digit() && y.is_ascii_digit()
+        {
+            true =>
+            {
+                let ((a, left_rest), (b, right_rest)) = (split_digits(left), split_digits(right));
+                (left, right) = (left_rest, right_rest);
+                let (a, b) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+                a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+            }
+            false =>
+            {
+                (left, right) = (&left[x.len_utf8()..], &right[y.len_utf8()..]);
+                x.to_lowercase().cmp(y.to_lowercase())
+            }
+        };
+        if order != cmp::Ordering::Equal
+        {
+            return order
+        }
+    }
+}
+
+fn natural_order(left: &Path, right: &Path) -> cmp::Ordering
+{
+    let (mut lefts, mut rights) = (left.components(), right.components());
+    loop
+    {
+        let order = match (lefts.next(), rights.next())
+        {
+            (None, None) => return left.cmp(right),
+            (None, Some(..)) => cmp::Ordering::Less,
+            (Some(..), None) => cmp::Ordering::Greater,
+            (Some(x), Some(y)) => natural_str_order
+            (
+                &x.as_os_str().to_string_lossy(),
+                &y.as_os_str().to_string_lossy()
+            )
+        };
+        if order != cmp::Ordering::Equal
+        {
+            return order
+        }
+    }
+}
+
+type DateTaken = (u16, u8, u8, u8, u8, u8, u32);
+
+fn date_taken(path: &Path) -> Option<DateTaken>
+{
+    const TAGS: [exif::Tag; 3] =
+        [exif::Tag::DateTimeOriginal, exif::Tag::DateTimeDigitized, exif::Tag::DateTime];
+    let mut reader = fs::File::open(path).map(io::BufReader::new).ok()?;
+    let fields = containers::exif_fields(&mut reader, &TAGS);
+    TAGS.into_iter()
+        .find_map
+        (
+            |tag| match &fields.iter().find(|(field, _)| *field == tag)?.1
+            {
+                exif::Value::Ascii(values) => values.first()
+                    .and_then(|value| exif::DateTime::from_ascii(value).ok()),
+                _ => None
+            }
+        )
+        .map
+        (
+            |date| (date.year, date.month, date.day, date.hour,
+                date.minute, date.second, date.nanosecond.unwrap_or(0))
+        )
+}
+
+#[derive(Clone, Default)]
+struct CachedKeys
+{
+    modified: Option<Option<SystemTime>>,
+    size: Option<Option<u64>>,
+    taken: Option<Option<DateTaken>>
+}
+
+#[derive(Clone, Default)]
+struct SortKeys(HashMap<PathBuf, CachedKeys>);
+
+impl SortKeys
+{
+    fn get<K: Copy>
+    (
+        &mut self,
+        path: &Path,
+        field: fn(&mut CachedKeys) -> &mut Option<K>,
+        read: fn(&Path) -> K
+    ) -> K
+    {
+        let cached = field(self.0.entry(path.to_owned()).or_default());
+        *cached.get_or_insert_with(|| read(path))
+    }
+
+    fn forget(&mut self, path: &Path)
+    {
+        self.0.remove(path);
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct NavigatorOptions
+{
+    pub extensions: Vec<&'static str>,
+    pub sniffer: Option<fn(&Path) -> bool>,
+    pub order: SortOrder,
+    pub recursive: bool
+}
+
+impl NavigatorOptions
+{
+    pub fn new(extensions: Vec<&'static str>) -> Self
+    {
+        Self{extensions, sniffer: None, order: SortOrder::default(), recursive: false}
+    }
+
+    pub fn accepts<P: AsRef<Path>>(&self, path: P) -> bool
+    {
+        let path = path.as_ref();
+        match path.extension()
+        {
+            Some(extension) => self.extensions.iter()
+                .any(|x| extension.eq_ignore_ascii_case(x)),
+            None => self.sniffer.is_some_and(|sniff| sniff(path))
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub enum FilepathsSource
+{
+    Path(PathBuf),
+    Playlist(Vec<PathBuf>)
+}
+
+impl FilepathsSource
+{
+    pub fn from_paths(mut paths: Vec<PathBuf>) -> Self
+    {
+        match paths.len()
+        {
+            0 => Self::Path(PathBuf::new()),
+            1 => Self::Path(paths.remove(0)),
+            _ => Self::Playlist(paths)
+        }
+    }
+
+    pub fn from_args<I>(args: I) -> NavigatorResult<Self>
+    where I: IntoIterator<Item = String>
+    {
+        let args: Vec<String> = args.into_iter().collect();
+        match args.as_slice()
+        {
+            [] => return Ok(Self::Path(PathBuf::new())),
+            [arg] if arg != "-" && !arg.starts_with('@')
+                => return Ok(Self::Path(PathBuf::from(arg))),
+            _ => {}
+        }
+        let directory = std::env::current_dir().map_err(NavigatorError::IO)?;
+        let mut paths = vec![];
+        for arg in args
+        {
+            match arg.strip_prefix('@')
+            {
+                _ if arg == "-" => paths.extend(Self::read_list(io::stdin().lock(), &directory)?),
+                Some(list) =>
+                {
+                    let list = directory.join(list);
+                    let reader = fs::File::open(&list)
+                        .map(io::BufReader::new)
+                        .map_err(NavigatorError::IO)?;
+                    paths.extend(Self::read_list(reader, list.parent().unwrap_or(&directory))?)
+                }
+                None => paths.push(directory.join(arg))
+            }
+        }
+        Ok(Self::Playlist(paths))
+    }
+
+    fn read_list<R>(reader: R, base: &Path) -> NavigatorResult<Vec<PathBuf>>
+    where R: io::BufRead
+    {
+        let mut paths = vec![];
+        for line in reader.lines()
+        {
+            let line = line.map_err(NavigatorError::IO)?;
+            if !line.is_empty() && !line.starts_with('#')
+            {
+                paths.push(base.join(line))
+            }
+        }
+        Ok(paths)
+    }
+
+    fn watched(&self, recursive: bool) -> Vec<(PathBuf, bool)>
+    {
+        let mut targets = vec![];
+        let paths = match self
+        {
+            Self::Path(directory) => std::slice::from_ref(directory),
+            Self::Playlist(paths) => paths.as_slice()
+        };
+        for path in paths
+        {
+            let target = match FileType::from(path)
+            {
+                FileType::Directory => (path.clone(), recursive),
+                FileType::File | FileType::Archive => match path.parent()
+                {
+                    Some(parent) => (parent.to_path_buf(), false),
+                    None => continue
+                }
+                _ => continue
+            };
+            if !targets.iter().any(|(path, _)| path == &target.0)
+            {
+                targets.push(target)
+            }
+        }
+        targets
+    }
+
+    fn rename(&mut self, source: &Path, destination: &Path)
+    {
+        if let Self::Playlist(paths) = self
+        {
+            for path in paths.iter_mut().filter(|path| path.as_path() == source)
+            {
+                *path = destination.to_path_buf()
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
 struct Filepaths(Vec<PathBuf>);
 
 impl Filepaths
 {
-    fn from_path<P: AsRef<Path>>(path: P) -> NavigatorResult<Self>
+    fn from_directory(directory: &Path, recursive: bool) -> NavigatorResult<Self>
     {
-        let filepaths = 
-            fs::read_dir(&FileType::as_dirpath(path)?)
+        let mut filepaths = vec![];
+        let mut directories = vec![];
+        for entry in fs::read_dir(directory)
             .map_err(NavigatorError::IO)?
             .filter_map(|entry| entry.ok())
-            .map(|entry| entry.path())
-            .filter(|path| path.is_file())
-            .collect();
+        {
+            let path = entry.path();
+            match entry.file_type()
+            {
+                Ok(file_type) if recursive && file_type.is_dir() => directories.push(path),
+                _ if path.is_file() => filepaths.push(path),
+                _ => {}
+            }
+        }
+        for directory in directories
+        {
+            if let Ok(Self(nested)) = Self::from_directory(&directory, recursive)
+            {
+                filepaths.extend(nested)
+            }
+        }
+        Ok(Self(filepaths))
+    }
+
+    fn from_archive(archive: &Path) -> NavigatorResult<Self>
+    {
+        archives::entries(archive)
+            .map(Self)
+            .map_err(NavigatorError::IO)
+    }
+
+    fn from_source(source: &FilepathsSource, recursive: bool) -> NavigatorResult<Self>
+    {
+        let paths = match source
+        {
+            FilepathsSource::Path(archive) if archive.is_file() => return Self::from_archive(archive),
+            FilepathsSource::Path(directory) => return Self::from_directory(directory, recursive),
+            FilepathsSource::Playlist(paths) => paths
+        };
+        let mut filepaths = vec![];
+        let mut seen = HashSet::new();
+        for path in paths
+        {
+            let found = match FileType::from(path)
+            {
+                FileType::Directory => Self::from_directory(path, recursive)
+                    .map_or_else
+                    (
+                        |_| vec![],
+                        |Self(mut nested)|
+                        {
+                            nested.sort_by(|x, y| natural_order(x, y));
+                            nested
+                        }
+                    ),
+                FileType::Archive => Self::from_archive(path)
+                    .map_or_else(|_| vec![], |Self(entries)| entries),
+                FileType::File => vec![path.clone()],
+                _ => vec![]
+            };
+            filepaths.extend(found.into_iter().filter(|path| seen.insert(path.clone())))
+        }
         Ok(Self(filepaths))
     }
     
@@ -141,24 +532,63 @@ impl Filepaths
         self.0.iter().position(|p| p == path.as_ref())
     }
     
-    fn filter_by_extensions
+    fn filter
     (
         &mut self, 
-        list: &Vec<&'static str>
+        options: &NavigatorOptions
     ) -> ()
     {
-        let predicate = |path: &PathBuf| match path.extension()
-        {
-            Some(extension) => list.iter()
-                .any(|x| extension.eq_ignore_ascii_case(x)),
-            None => false
-        };
-        self.0.retain(predicate)
+        self.0.retain(|path| options.accepts(path))
     }
     
-    fn sort(&mut self) -> ()
+    fn sort_by_key<K, F>(&mut self, mut key: F)
+    where
+        K: Ord,
+        F: FnMut(&Path) -> K
     {
-        self.0.sort()
+        let mut keyed: Vec<_> = self.0.drain(..)
+            .map(|path| (key(&path), path))
+            .collect();
+        keyed.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| natural_order(x, y)));
+        self.0 = keyed.into_iter().map(|(_, path)| path).collect()
+    }
+
+    fn sort(&mut self, order: SortOrder, keys: &mut SortKeys)
+    {
+        match order.key
+        {
+            SortKey::Name => self.0.sort_by(|x, y| natural_order(x, y)),
+            SortKey::Modified => self.sort_by_key
+            (
+                |path| keys.get
+                (
+                    path,
+                    |cached| &mut cached.modified,
+                    |path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+                )
+            ),
+            SortKey::Size => self.sort_by_key
+            (
+                |path| keys.get
+                (
+                    path,
+                    |cached| &mut cached.size,
+                    |path| fs::metadata(path).map(|metadata| metadata.len()).ok()
+                )
+            ),
+            SortKey::DateTaken => self.sort_by_key
+            (
+                |path|
+                {
+                    let date = keys.get(path, |cached| &mut cached.taken, date_taken);
+                    (date.is_none(), date)
+                }
+            )
+        }
+        if order.reverse
+        {
+            self.0.reverse()
+        }
     }
 }
 
@@ -166,48 +596,181 @@ impl Filepaths
 
 pub struct FilepathsNavigator
 {
+    source: FilepathsSource,
     filepaths: Filepaths,
-    extensions: Vec<&'static str>,
+    options: NavigatorOptions,
     cursor: usize,
+    sorted: bool,
+    sort_keys: SortKeys,
     watcher: Watcher
 }
 
 impl FilepathsNavigator
 {
-    pub fn from_path<P: AsRef<Path>>
+    pub fn from_source
     (
-        path: P,
-        extensions: &Vec<&'static str>
+        source: FilepathsSource,
+        options: &NavigatorOptions,
+        waker: Waker
     ) -> NavigatorResult<Self>
     {
-        let path = path.as_ref().to_path_buf();
-        let mut filepaths = Filepaths::from_path(&path)?;
-        filepaths.filter_by_extensions(extensions);
-        filepaths.sort();
-        let cursor = match path.is_file()
+        let path = match source
         {
-            true => match filepaths.search_for(&path)
+            FilepathsSource::Path(path) => path,
+            playlist =>
             {
-                Some(index) => index,
-                None => return Err(NavigatorError::NoMatchingEntry(path))
+                let this = Self::open(playlist, options, SortKeys::default(), waker)?;
+                this.nonempty()?;
+                return Ok(this)
             }
-            false => 0
         };
-        let extensions = extensions.clone();
-        let watcher = Watcher::watch(&FileType::as_dirpath(path)?).map_err(NavigatorError::Notify)?;
-        let this = Self{filepaths, extensions, cursor, watcher};
+        let mut this = Self::open
+        (
+            FilepathsSource::Path(FileType::as_dirpath(&path)?),
+            options,
+            SortKeys::default(),
+            waker
+        )?;
+        if matches!(FileType::from(&path), FileType::File) && !this.select(&path)
+        {
+            return Err(NavigatorError::NoMatchingEntry(path))
+        }
         this.nonempty()?;
         Ok(this)
     }
 
-    pub fn navigate<D>(&mut self, direction: D) -> ()
+    pub fn reopen(&self, options: &NavigatorOptions, waker: Waker) -> NavigatorResult<Self>
+    {
+        let mut this = Self::open(self.source.clone(), options, self.sort_keys.clone(), waker)?;
+        this.nonempty()?;
+        if self.sorted && !this.sorted
+        {
+            this.sort(options.order)
+        }
+        match (this.select(self.selected()), &self.source)
+        {
+            (true, _) | (false, FilepathsSource::Playlist(..)) => Ok(this),
+            (false, FilepathsSource::Path(..))
+                => Err(NavigatorError::NoMatchingEntry(self.selected().clone()))
+        }
+    }
+
+    fn open
+    (
+        source: FilepathsSource,
+        options: &NavigatorOptions,
+        mut sort_keys: SortKeys,
+        waker: Waker
+    ) -> NavigatorResult<Self>
+    {
+        let mut filepaths = Filepaths::from_source(&source, options.recursive)?;
+        filepaths.filter(options);
+        let sorted = matches!(source, FilepathsSource::Path(..));
+        if sorted
+        {
+            filepaths.sort(options.order, &mut sort_keys)
+        }
+        let options = options.clone();
+        let watcher = Watcher::watch(source.watched(options.recursive), waker)
+            .map_err(NavigatorError::Notify)?;
+        Ok(Self{source, filepaths, options, cursor: 0, sorted, sort_keys, watcher})
+    }
+
+    fn select(&mut self, path: &Path) -> bool
+    {
+        match self.filepaths.search_for(path)
+        {
+            Some(index) =>
+            {
+                self.cursor = index;
+                true
+            }
+            None => false
+        }
+    }
+
+    fn offset<D>(&self, direction: D) -> usize
     where D: Into<i8>
     {
         let len = self.filepaths.0.len();
-        self.cursor = (self.cursor as i64 + direction.into() as i64)
+        (self.cursor as i64 + direction.into() as i64)
             .rem_euclid(len as _) as _
     }
 
+    pub fn navigate<D>(&mut self, direction: D)
+    where D: Into<i8>
+    {
+        self.cursor = self.offset(direction)
+    }
+
+    pub fn neighbors(&self) -> Vec<PathBuf>
+    {
+        let mut neighbors = vec![];
+        for index in [self.offset(1), self.offset(-1)]
+        {
+            let filepath = &self.filepaths.0[index];
+            if index != self.cursor && !neighbors.contains(filepath)
+            {
+                neighbors.push(filepath.clone())
+            }
+        }
+        neighbors
+    }
+
+    pub fn sort(&mut self, order: SortOrder)
+    {
+        let selected = self.selected().clone();
+        self.filepaths.sort(order, &mut self.sort_keys);
+        self.cursor = self.filepaths.search_for(selected).unwrap_or(0);
+        self.options.order = order;
+        self.sorted = true
+    }
+
+    pub fn sibling_directory<D>(&self, direction: D) -> NavigatorResult<Option<PathBuf>>
+    where D: Into<i8>
+    {
+        let directory = match &self.source
+        {
+            FilepathsSource::Path(directory)
+                => fs::canonicalize(directory).map_err(NavigatorError::IO)?,
+            FilepathsSource::Playlist(..) => return Ok(None)
+        };
+        let parent = match directory.parent()
+        {
+            Some(parent) => parent,
+            None => return Ok(None)
+        };
+        let mut directories: Vec<PathBuf> = fs::read_dir(parent)
+            .map_err(NavigatorError::IO)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+            .map(|entry| entry.path())
+            .collect();
+        directories.sort_by(|x, y| natural_order(x, y));
+        let position = match directories.iter().position(|path| path == &directory)
+        {
+            Some(position) => position as i64,
+            None => return Ok(None)
+        };
+        let direction = direction.into() as i64;
+        let len = directories.len() as i64;
+        let sibling = (1..len)
+            .map(|step| &directories[(position + step * direction).rem_euclid(len) as usize])
+            .find
+            (
+                |sibling| match Filepaths::from_directory(sibling, self.options.recursive)
+                {
+                    Ok(mut filepaths) =>
+                    {
+                        filepaths.filter(&self.options);
+                        !filepaths.0.is_empty()
+                    }
+                    Err(..) => false
+                }
+            );
+        Ok(sibling.cloned())
+    }
+
     fn nonempty(&self) -> NavigatorResult<()>
     {
         (!self.filepaths.0.is_empty()).then(|| ())
@@ -221,12 +784,15 @@ impl FilepathsNavigator
 
     fn rescan(&mut self) -> NavigatorResult<()>
     {
-        let selected = self.selected();
-        let mut filepaths = Filepaths::from_path(selected)?;
-        filepaths.filter_by_extensions(&self.extensions);
-        filepaths.sort();
-        let cursor = filepaths.search_for(selected)
-            .ok_or(NavigatorError::NoMatchingEntry(selected.clone()))?;
+        let selected = self.selected().clone();
+        let mut filepaths = Filepaths::from_source(&self.source, self.options.recursive)?;
+        filepaths.filter(&self.options);
+        if self.sorted
+        {
+            filepaths.sort(self.options.order, &mut self.sort_keys)
+        }
+        let cursor = filepaths.search_for(&selected)
+            .ok_or(NavigatorError::NoMatchingEntry(selected))?;
         self.filepaths = filepaths;
         self.cursor = cursor;
         self.nonempty()
@@ -241,12 +807,21 @@ impl FilepathsNavigator
         {
             match received
             {
-                Write(path) if &path == self.selected()
-                    => dirty = true,
-                Rescan | Chmod(..) | Create(..) => rescan = true,
+                Write(path) =>
+                {
+                    self.sort_keys.forget(&path);
+                    dirty |= &path == self.selected()
+                }
+                Rescan =>
+                {
+                    self.sort_keys = SortKeys::default();
+                    rescan = true
+                }
+                Chmod(..) | Create(..) => rescan = true,
                 Remove(path) => if let Some(index) =
                     self.filepaths.search_for(&path)
                 {
+                    self.sort_keys.forget(&path);
                     self.filepaths.0.remove(index);
                     self.nonempty()?;
                     if index == self.cursor
@@ -262,6 +837,8 @@ impl FilepathsNavigator
                 Rename(source, destination)
                     if &source == self.selected() =>
                 {
+                    self.sort_keys.forget(&source);
+                    self.source.rename(&source, &destination);
                     self.filepaths.0[self.cursor] = destination;
                     rescan = true
                 }
@@ -738,12 +738,29 @@ pub fn fill_texture<T: TextureBaseDataType>
     mimap: bool,
     image: Image<T>
 ) -> ()
+{
+    let resolution = image.resolution;
+    fill_texture_region(pointers, texture, mimap, image, [0, 0], resolution)
+}
+
+pub fn fill_texture_region<T: TextureBaseDataType>
+(
+    pointers: &FunctionPointers,
+    texture: &Texture,
+    mimap: bool,
+    image: Image<T>,
+    offset: [u32; 2],
+    size: [u32; 2]
+)
 {
     use ChannelCount::*;
     let previously_bound = gl_get(pointers, TEXTURE_BINDING_2D);
     unsafe
     {
         pointers.BindTexture(TEXTURE_2D, **texture);
+        pointers.PixelStorei(UNPACK_ROW_LENGTH, image.resolution[0] as _);
+        pointers.PixelStorei(UNPACK_SKIP_PIXELS, offset[0] as _);
+        pointers.PixelStorei(UNPACK_SKIP_ROWS, offset[1] as _);
         pointers.TexImage2D
         (
             TEXTURE_2D,
@@ -755,8 +772,8 @@ pub fn fill_texture<T: TextureBaseDataType>
                 UNSIGNED_INT | INT | FLOAT => RGBA32F,
                 _ => unreachable!("Uncovered type")
             } as _,
-            image.resolution[0] as _,
-            image.resolution[1] as _,
+            size[0] as _,
+            size[1] as _,
             0,
             match image.channel_count
             {
@@ -772,6 +789,9 @@ pub fn fill_texture<T: TextureBaseDataType>
                 None => 0 as _
             }
         );
+        pointers.PixelStorei(UNPACK_ROW_LENGTH, 0);
+        pointers.PixelStorei(UNPACK_SKIP_PIXELS, 0);
+        pointers.PixelStorei(UNPACK_SKIP_ROWS, 0);
         if mimap {pointers.GenerateMipmap(TEXTURE_2D)}
         pointers.BindTexture(TEXTURE_2D, previously_bound as _)
     }
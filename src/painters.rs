@@ -1,5 +1,5 @@
 
-use {std::str::*, super::ogl::*};
+use {std::{str::*, fmt}, super::ogl::*};
 
 // ------------------------------------------------------------
 
@@ -170,11 +170,238 @@ impl Filler
 
 // ------------------------------------------------------------
 
+#[derive(Clone, Copy)]
+pub enum ToneMapping
+{
+    Clip,
+    Reinhard,
+    Filmic
+}
+
+impl ToneMapping
+{
+    pub fn next(&self) -> Self
+    {
+        match self
+        {
+            Self::Clip => Self::Reinhard,
+            Self::Reinhard => Self::Filmic,
+            Self::Filmic => Self::Clip
+        }
+    }
+
+    pub fn map(&self, radiance: f32) -> f32
+    {
+        match self
+        {
+            Self::Clip => radiance.clamp(0.0, 1.0),
+            Self::Reinhard => radiance / (1.0 + radiance),
+            Self::Filmic =>
+            (
+                (radiance * (2.51 * radiance + 0.03)) /
+                (radiance * (2.43 * radiance + 0.59) + 0.14)
+            ).clamp(0.0, 1.0)
+        }
+    }
+}
+
+impl fmt::Display for ToneMapping
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::Clip => write!(formatter, "Clip"),
+            Self::Reinhard => write!(formatter, "Reinhard"),
+            Self::Filmic => write!(formatter, "Filmic")
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+pub struct Tone
+{
+    pub exposure: f32,
+    pub mapping: ToneMapping
+}
+
+impl Default for Tone
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            exposure: 0.0,
+            mapping: ToneMapping::Clip
+        }
+    }
+}
+
+impl Tone
+{
+    pub fn apply(&self, radiance: f32) -> f32
+    {
+        self.mapping.map(radiance.max(0.0) * self.exposure.exp2())
+    }
+}
+
+impl fmt::Display for Tone
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(formatter, "{:+.1} EV  {}", self.exposure, self.mapping)
+    }
+}
+
+// ------------------------------------------------------------
+
+struct Tile
+{
+    texture: Texture,
+    origin: [u32; 2],
+    size: [u32; 2],
+    border: [u32; 2],
+    extent: [u32; 2]
+}
+
+struct TiledTexture
+{
+    pointers: FunctionPointers,
+    resolution: [u32; 2],
+    tiles: Vec<Tile>
+}
+
+impl TiledTexture
+{
+    const OVERLAP_LEVELS: u32 = 4;
+    const OVERLAP: u32 = 1 << Self::OVERLAP_LEVELS;
+
+    fn new(pointers: &FunctionPointers) -> Self
+    {
+        Self
+        {
+            pointers: pointers.clone(),
+            resolution: [0, 0],
+            tiles: vec![]
+        }
+    }
+
+    fn fill<T: TextureBaseDataType>(&mut self, image: Image<T>)
+    {
+        let limit = (gl_get(&self.pointers, MAX_TEXTURE_SIZE) as u32).max(4 * Self::OVERLAP);
+        let resolution = image.resolution;
+        let span = resolution.map
+        (
+            |length| match length <= limit
+            {
+                true => limit,
+                false => limit - 2 * Self::OVERLAP
+            }
+        );
+        let grid = [0, 1].map(|axis| resolution[axis].div_ceil(span[axis]).max(1));
+        let max_level = match grid
+        {
+            [1, 1] => 1000,
+            _ => Self::OVERLAP_LEVELS
+        };
+        let count = (grid[0] * grid[1]) as usize;
+        self.tiles.truncate(count);
+        while self.tiles.len() < count
+        {
+            let texture = create_texture
+            (
+                &self.pointers,
+                Some(WrapMode::ClampToEdge),
+                InterpolationType::Linear,
+                InterpolationType::Linear,
+                Some(InterpolationType::Nearest)
+            );
+            self.tiles.push
+            (
+                Tile{texture, origin: [0, 0], size: [0, 0], border: [0, 0], extent: [0, 0]}
+            )
+        }
+        for (index, tile) in self.tiles.iter_mut().enumerate()
+        {
+            let cell = [index as u32 % grid[0], index as u32 / grid[0]];
+            tile.origin = [cell[0] * span[0], cell[1] * span[1]];
+            tile.size = [0, 1].map(|axis| span[axis].min(resolution[axis] - tile.origin[axis]));
+            tile.border = tile.origin.map(|origin| (origin > 0) as u32 * Self::OVERLAP);
+            tile.extent = [0, 1].map
+            (
+                |axis| tile.border[axis] + tile.size[axis] +
+                    (tile.origin[axis] + tile.size[axis] < resolution[axis]) as u32 * Self::OVERLAP
+            );
+            unsafe
+            {
+                self.pointers.BindTexture(TEXTURE_2D, *tile.texture);
+                self.pointers.TexParameteri(TEXTURE_2D, TEXTURE_MAX_LEVEL, max_level as _);
+            }
+            fill_texture_region
+            (
+                &self.pointers,
+                &tile.texture,
+                true,
+                Image
+                {
+                    data: image.data,
+                    resolution,
+                    channel_count: image.channel_count
+                },
+                [0, 1].map(|axis| tile.origin[axis] - tile.border[axis]),
+                tile.extent
+            )
+        }
+        self.resolution = resolution
+    }
+
+    fn draw(&self, canvas: &Canvas, viewport: &GLViewport)
+    {
+        let edge = |axis: usize, at: u32|
+            (viewport.size[axis] as f64 * at as f64 / self.resolution[axis] as f64)
+                .round() as i32;
+        for tile in &self.tiles
+        {
+            let left = edge(0, tile.origin[0]);
+            let right = edge(0, tile.origin[0] + tile.size[0]);
+            let top = edge(1, tile.origin[1]);
+            let bottom = edge(1, tile.origin[1] + tile.size[1]);
+            let [inset, scale] = [tile.border, tile.size]
+                .map(|texels| [0, 1].map(|axis| texels[axis] as f32 / tile.extent[axis] as f32));
+            canvas.set_uniform("region", [inset[0], inset[1], scale[0], scale[1]]);
+            unsafe
+            {
+                self.pointers.ActiveTexture(TEXTURE0);
+                self.pointers.BindTexture(TEXTURE_2D, *tile.texture);
+            }
+            canvas.draw
+            (
+                &GLViewport
+                {
+                    origin:
+                    [
+                        viewport.origin[0] + left,
+                        viewport.origin[1] + viewport.size[1] as i32 - bottom
+                    ],
+                    size: [(right - left) as u32, (bottom - top) as u32]
+                }
+            )
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
 pub struct Blitter
 {
     pointers: FunctionPointers,
     canvas: Canvas,
-    texture: Texture
+    texture: TiledTexture,
+    resident: Vec<TiledTexture>,
+    selected: Option<usize>,
+    high_dynamic_range: bool
 }
 
 impl Blitter
@@ -189,69 +416,153 @@ impl Blitter
             in vec2 st;
             out vec4 color;
             uniform sampler2D image;
+            uniform vec4 region;
             uniform ivec4 order;
             uniform float gamma;
-            void main()
+            uniform bool high_dynamic_range;
+            uniform float exposure;
+            uniform int tone_mapping;
+            vec3 map_tone(vec3 radiance)
             {
-                for(int channel = 0; channel < 4; channel++)
+                switch(tone_mapping)
                 {
-                    color[channel] = pow
+                    case 1: return radiance / (1.0 + radiance);
+                    case 2: return clamp
                     (
-                        texture
-                        (
-                            image,
-                            vec2(st.x, 1.0 - st.y)
-                        )[order[channel]],
-                        gamma
+                        (radiance * (2.51 * radiance + 0.03)) /
+                        (radiance * (2.43 * radiance + 0.59) + 0.14),
+                        0.0,
+                        1.0
                     );
+                    default: return clamp(radiance, 0.0, 1.0);
+                }
+            }
+            void main()
+            {
+                vec4 texel = texture
+                (
+                    image,
+                    region.xy + vec2(st.x, 1.0 - st.y) * region.zw
+                );
+                for(int channel = 0; channel < 4; channel++)
+                {
+                    color[channel] = texel[order[channel]];
                 }
+                if(high_dynamic_range)
+                {
+                    color.rgb = map_tone(max(color.rgb, 0.0) * exp2(exposure));
+                }
+                color.rgb = pow(color.rgb, vec3(gamma));
             }
             "
         );
         canvas.set_uniform("image", 0i32);
-        let texture = create_texture
-        (
-            pointers,
-            None,
-            InterpolationType::Linear,
-            InterpolationType::Linear,
-            Some(InterpolationType::Nearest)
-        );
-        Self
+        canvas.set_uniform("high_dynamic_range", 0i32);
+        let mut this = Self
         {
             pointers: pointers.clone(),
             canvas,
-            texture
-        }
+            texture: TiledTexture::new(pointers),
+            resident: vec![],
+            selected: None,
+            high_dynamic_range: false
+        };
+        this.set_tone(&Default::default());
+        this
     }
 
-    pub fn upload_texture<T: TextureBaseDataType>
+    pub fn set_tone(&mut self, tone: &Tone)
+    {
+        self.canvas.set_uniform("exposure", tone.exposure);
+        self.canvas.set_uniform("tone_mapping", tone.mapping as i32)
+    }
+
+    pub fn is_high_dynamic_range(&self) -> bool
+    {
+        self.high_dynamic_range
+    }
+
+    fn set_format<T: TextureBaseDataType>
     (
-        &mut self, 
-        image: Image<T>,
+        &mut self,
         channel_order: [i32; 4],
         gamma: f32
     ) -> ()
     {
+        self.high_dynamic_range = T::TYPE_ENUM == FLOAT;
         self.canvas.set_uniform("order", channel_order);
-        self.canvas.set_uniform("gamma", gamma);
-        fill_texture
+        self.canvas.set_uniform
         (
-            &self.pointers,
-            &self.texture,
-            true,
-            image
+            "gamma",
+            match self.high_dynamic_range
+            {
+                true => gamma,
+                false => 1.0
+            }
+        );
+        self.canvas.set_uniform
+        (
+            "high_dynamic_range",
+            self.high_dynamic_range as i32
         );
     }
 
-    pub fn blit(&self, viewport: &GLViewport) -> ()
+    pub fn upload_texture<T: TextureBaseDataType>
+    (
+        &mut self, 
+        image: Image<T>,
+        channel_order: [i32; 4],
+        gamma: f32
+    )
     {
-        unsafe
+        self.set_format::<T>(channel_order, gamma);
+        self.release_resident_textures();
+        self.texture.fill(image)
+    }
+
+    pub fn upload_resident_texture<T: TextureBaseDataType>
+    (
+        &mut self,
+        index: usize,
+        image: Image<T>,
+        channel_order: [i32; 4],
+        gamma: f32
+    )
+    {
+        self.set_format::<T>(channel_order, gamma);
+        self.resident.truncate(index.max(1));
+        while self.resident.len() <= index
         {
-            self.pointers.ActiveTexture(TEXTURE0);
-            self.pointers.BindTexture(TEXTURE_2D, *self.texture);
+            self.resident.push(TiledTexture::new(&self.pointers))
         }
-        self.canvas.draw(viewport)
+        self.resident[index].fill(image);
+        self.selected = Some(index)
+    }
+
+    pub fn select_resident_texture(&mut self, index: usize) -> bool
+    {
+        let available = index < self.resident.len();
+        if available
+        {
+            self.selected = Some(index)
+        }
+        available
+    }
+
+    pub fn release_resident_textures(&mut self)
+    {
+        self.resident.clear();
+        self.selected = None
+    }
+
+    pub fn blit(&self, viewport: &GLViewport) -> ()
+    {
+        let texture = match self.selected
+        {
+            Some(index) => &self.resident[index],
+            None => &self.texture
+        };
+        texture.draw(&self.canvas, viewport)
     }
 }
 
@@ -371,7 +682,7 @@ impl Paragraph
     {
         let paragraph = textwrap::fill(text, wrap as usize);
         let lines = paragraph.lines();
-        let num_lines = lines.clone().count() - 1;
+        let num_lines = lines.clone().count().saturating_sub(1);
         let top = self.rasterizer.leading as i32 * num_lines as i32;
         let mut glyphs = vec![];
         for (line_index, line) in lines.enumerate()
@@ -1,12 +1,18 @@
 
 use
 {
-    std::{io, fmt, time::*},
-    super::ogl,
+    std::{io, fmt, time::*, rc::Rc, sync::{Arc, atomic::*, mpsc::*}},
+    super::
+    {
+        ogl,
+        utility::{Waker, SharedWaker, physical_memory},
+        containers,
+        archives,
+        decoders::{DecoderRegistry, PictureRead},
+        progressive::PartialSink
+    },
     image::
     {
-        ImageFormat::*,
-        codecs::*,
         GenericImageView,
         DynamicImage::*,
         ImageDecoder
@@ -21,10 +27,13 @@ pub enum PictureError
     IO(std::io::Error),
     ImageError(image::error::ImageError),
     ICCError(lcms2::Error),
+    MalformedICC(lcms2::Error),
     UnsupportedChannelCount(u8),
     UnsupportedImageFormat,
     UnsupportedPixelFormat,
-    ZeroFrames
+    ExceedsBudget(PictureDimensions, DecodeBudget),
+    ZeroFrames,
+    MissingResidentFrame(usize)
 }
 
 impl std::error::Error for PictureError {}
@@ -38,14 +47,23 @@ impl fmt::Display for PictureError
             Self::IO(error) => write!(formatter, "{}", error),
             Self::ImageError(error) => write!(formatter, "{error}"),
             Self::ICCError(error) => write!(formatter, "ICC error: {error}"),
+            Self::MalformedICC(error)
+                => write!(formatter, "Malformed embedded ICC profile: {error}"),
             Self::UnsupportedChannelCount(count)
                 => write!(formatter, "Unsupported channel count {count}"),
             Self::UnsupportedImageFormat
                 => write!(formatter, "Unsupported image format"),
             Self::UnsupportedPixelFormat
                 => write!(formatter, "Unsupported pixel format"),
+            Self::ExceedsBudget([width, height], budget) => write!
+            (
+                formatter,
+                "The {width}x{height} image exceeds the decoding budget of {budget}"
+            ),
             Self::ZeroFrames
-                => write!(formatter, "Animated image has no frames")
+                => write!(formatter, "Animated image has no frames"),
+            Self::MissingResidentFrame(index)
+                => write!(formatter, "Frame {} is no longer resident", index + 1)
         }
     }
 }
@@ -62,6 +80,101 @@ pub type PictureResult<T> = std::result::Result<T, PictureError>;
 
 // ------------------------------------------------------------
 
+pub fn embedded_icc(icc: Option<Vec<u8>>) -> PictureResult<lcms2::Profile>
+{
+    icc.map_or
+    (
+        Ok(lcms2::Profile::new_srgb()),
+        |icc| lcms2::Profile::new_icc(&icc)
+            .map_err(PictureError::MalformedICC)
+    )
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug)]
+pub struct DecodeBudget
+{
+    pub pixels: u64,
+    pub bytes: u64
+}
+
+impl Default for DecodeBudget
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            pixels: 1_000_000_000,
+            bytes: physical_memory()
+                .map_or(Self::FALLBACK_BYTES, |memory| (memory / 2).max(Self::MIN_BYTES))
+        }
+    }
+}
+
+impl fmt::Display for DecodeBudget
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!
+        (
+            formatter,
+            "{} megapixels and {} MiB",
+            self.pixels / 1_000_000,
+            self.bytes >> 20
+        )
+    }
+}
+
+impl DecodeBudget
+{
+    pub const MAX_BYTES_PER_PIXEL: u64 = 16;
+    const MIN_BYTES: u64 = 512 << 20;
+    const FALLBACK_BYTES: u64 = 4 << 30;
+
+    pub fn from_env() -> Self
+    {
+        let read = |name: &str| std::env::var(name).ok()
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        let default = Self::default();
+        Self
+        {
+            pixels: read("OCHRA_MAX_MEGAPIXELS")
+                .and_then(|megapixels| megapixels.checked_mul(1_000_000))
+                .unwrap_or(default.pixels),
+            bytes: read("OCHRA_MAX_MEMORY_MB")
+                .and_then(|megabytes| megabytes.checked_mul(1 << 20))
+                .unwrap_or(default.bytes)
+        }
+    }
+
+    pub fn check(&self, dimensions: PictureDimensions, bytes_per_pixel: u64) -> PictureResult<()>
+    {
+        let pixels = dimensions[0] as u64 * dimensions[1] as u64;
+        match pixels <= self.pixels && pixels.saturating_mul(bytes_per_pixel) <= self.bytes
+        {
+            true => Ok(()),
+            false => Err(PictureError::ExceedsBudget(dimensions, *self))
+        }
+    }
+
+    pub fn check_decoder<'a, D>(&self, decoder: &D) -> PictureResult<()>
+    where D: ImageDecoder<'a>
+    {
+        let (width, height) = decoder.dimensions();
+        self.check([width, height], decoder.color_type().bytes_per_pixel() as u64)
+    }
+
+    pub fn limits(&self) -> image::io::Limits
+    {
+        let mut limits = image::io::Limits::default();
+        limits.max_alloc = Some(self.bytes);
+        limits
+    }
+}
+
+// ------------------------------------------------------------
+
 impl TryFrom<u8> for ogl::ChannelCount
 {
     type Error = PictureError;
@@ -109,7 +222,86 @@ impl ChannelInterpretation
 pub enum PixelData
 {
     EightBit(Vec<u8>),
-    SixteenBit(Vec<u16>)
+    SixteenBit(Vec<u16>),
+    Float(Vec<f32>)
+}
+
+impl PixelData
+{
+    pub fn byte_len(&self) -> usize
+    {
+        match self
+        {
+            Self::EightBit(data) => data.len(),
+            Self::SixteenBit(data) => data.len() * 2,
+            Self::Float(data) => data.len() * 4
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+pub enum Orientation
+{
+    Normal,
+    FlipHorizontal,
+    Rotate180,
+    FlipVertical,
+    Transpose,
+    Rotate90,
+    Transverse,
+    Rotate270
+}
+
+impl From<u32> for Orientation
+{
+    fn from(tag: u32) -> Self
+    {
+        match tag
+        {
+            2 => Self::FlipHorizontal,
+            3 => Self::Rotate180,
+            4 => Self::FlipVertical,
+            5 => Self::Transpose,
+            6 => Self::Rotate90,
+            7 => Self::Transverse,
+            8 => Self::Rotate270,
+            _ => Self::Normal
+        }
+    }
+}
+
+impl Orientation
+{
+    pub fn read<R>(reader: &mut R) -> Self
+    where R: io::Read + io::Seek
+    {
+        containers::exif_fields(reader, &[exif::Tag::Orientation])
+            .first()
+            .and_then(|(_, value)| value.get_uint(0))
+            .map_or(Self::Normal, Self::from)
+    }
+
+    fn swaps_dimensions(&self) -> bool
+    {
+        matches!(self, Self::Transpose | Self::Rotate90 | Self::Transverse | Self::Rotate270)
+    }
+
+    pub fn apply(&self, dynamic_image: image::DynamicImage) -> image::DynamicImage
+    {
+        match self
+        {
+            Self::Normal => dynamic_image,
+            Self::FlipHorizontal => dynamic_image.fliph(),
+            Self::Rotate180 => dynamic_image.rotate180(),
+            Self::FlipVertical => dynamic_image.flipv(),
+            Self::Transpose => dynamic_image.rotate90().fliph(),
+            Self::Rotate90 => dynamic_image.rotate90(),
+            Self::Transverse => dynamic_image.rotate270().fliph(),
+            Self::Rotate270 => dynamic_image.rotate270()
+        }
+    }
 }
 
 // ------------------------------------------------------------
@@ -124,6 +316,47 @@ pub struct StillPicture
     pub icc: lcms2::Profile
 }
 
+fn shrink<T>
+(
+    data: &[T],
+    channels: usize,
+    [width, height]: PictureDimensions,
+    factor: u32,
+    convert: fn(f64) -> T
+) -> Vec<T>
+where T: Copy + Into<f64>
+{
+    let (width, height, factor) = (width as usize, height as usize, factor as usize);
+    let [columns, rows] = [width, height].map(|length| length.div_ceil(factor));
+    let mut shrunk = Vec::with_capacity(columns * rows * channels);
+    let mut sums = vec![0.0; columns * channels];
+    for row in 0..rows
+    {
+        sums.fill(0.0);
+        let lines = row * factor..((row + 1) * factor).min(height);
+        for line in lines.clone()
+        {
+            let pixels = data[line * width * channels..][..width * channels].chunks_exact(channels);
+            for (column, pixel) in pixels.enumerate()
+            {
+                for (sum, &value) in sums[column / factor * channels..].iter_mut().zip(pixel)
+                {
+                    *sum += value.into()
+                }
+            }
+        }
+        for column in 0..columns
+        {
+            let area = lines.len() * (((column + 1) * factor).min(width) - column * factor);
+            shrunk.extend
+            (
+                sums[column * channels..][..channels].iter().map(|&sum| convert(sum / area as f64))
+            )
+        }
+    }
+    shrunk
+}
+
 impl TryFrom<(lcms2::Profile, image::DynamicImage)> for StillPicture
 {
     type Error = PictureError;
@@ -160,8 +393,8 @@ impl TryFrom<(lcms2::Profile, image::DynamicImage)> for StillPicture
             ImageLumaA16(buffer) => SixteenBit(buffer.into_raw()),
             ImageRgb16(buffer) => SixteenBit(buffer.into_raw()),
             ImageRgba16(buffer) => SixteenBit(buffer.into_raw()),
-            ImageRgb32F(_) => SixteenBit(dynamic_image.into_rgb16().into_raw()),
-            ImageRgba32F(_) => SixteenBit(dynamic_image.into_rgba16().into_raw()),
+            ImageRgb32F(buffer) => Float(buffer.into_raw()),
+            ImageRgba32F(buffer) => Float(buffer.into_raw()),
             _ => return Err(PictureError::UnsupportedPixelFormat)
         };
         let this = Self
@@ -179,11 +412,91 @@ impl TryFrom<(lcms2::Profile, image::DynamicImage)> for StillPicture
 
 impl StillPicture
 {
-    pub fn transform_to_icc(&mut self, target: &lcms2::Profile) -> PictureResult<()>
+    pub fn try_clone(&self) -> PictureResult<Self>
+    {
+        Ok
+        (
+            Self
+            {
+                pixel_data: self.pixel_data.clone(),
+                resolution: self.resolution,
+                channel_count: self.channel_count,
+                channel_interpretation: self.channel_interpretation,
+                gamma: self.gamma,
+                icc: lcms2::Profile::new_icc(&self.icc.icc()?)?
+            }
+        )
+    }
+
+    pub fn downscaled(&self, pixels: u32) -> PictureResult<Option<Self>>
+    {
+        use {ogl::ChannelCount::*, PixelData::*};
+        let longest = self.resolution[0].max(self.resolution[1]);
+        if longest <= pixels
+        {
+            return Ok(None)
+        }
+        let factor = longest.div_ceil(pixels.max(1));
+        let channels = match self.channel_count
+        {
+            One => 1,
+            Two => 2,
+            Three => 3,
+            Four => 4
+        };
+        let pixel_data = match &self.pixel_data
+        {
+            EightBit(data) => EightBit
+            (
+                shrink(data, channels, self.resolution, factor, |value| value.round() as u8)
+            ),
+            SixteenBit(data) => SixteenBit
+            (
+                shrink(data, channels, self.resolution, factor, |value| value.round() as u16)
+            ),
+            Float(data) => Float
+            (
+                shrink(data, channels, self.resolution, factor, |value| value as f32)
+            )
+        };
+        Ok
+        (
+            Some
+            (
+                Self
+                {
+                    pixel_data,
+                    resolution: self.resolution.map(|length| length.div_ceil(factor)),
+                    channel_count: self.channel_count,
+                    channel_interpretation: self.channel_interpretation,
+                    gamma: self.gamma,
+                    icc: lcms2::Profile::new_icc(&self.icc.icc()?)?
+                }
+            )
+        )
+    }
+
+    pub fn to_icc(&self, target: &lcms2::Profile) -> PictureResult<Self>
+    {
+        Ok
+        (
+            Self
+            {
+                pixel_data: self.transform_pixels(target)?,
+                resolution: self.resolution,
+                channel_count: self.channel_count,
+                channel_interpretation: self.channel_interpretation,
+                gamma: self.gamma,
+                icc: lcms2::Profile::new_icc(&target.icc()?)?
+            }
+        )
+    }
+
+    fn transform_pixels(&self, target: &lcms2::Profile) -> PictureResult<PixelData>
     {
         use lcms2::PixelFormat;
         let intent = lcms2::Intent::Perceptual;
-        match &mut self.pixel_data
+        match &self.pixel_data
         {
             PixelData::EightBit(pixels_data) =>
             {
@@ -195,7 +508,7 @@ impl StillPicture
                         let mut pixels = pixels_data.clone();
                         lcms2::Transform::new(&self.icc, format, target, format, intent)
                             .map(|t| t.transform_in_place(&mut pixels))?;
-                        Ok(*pixels_data = pixels.into_iter().collect())
+                        Ok(PixelData::EightBit(pixels))
                     }
                     ChannelInterpretation::LA =>
                     {
@@ -205,7 +518,7 @@ impl StillPicture
                             .collect::<Vec<[u8; 2]>>();
                         lcms2::Transform::new(&self.icc, format, target, format, intent)
                             .map(|t| t.transform_in_place(&mut pixels))?;
-                        Ok(*pixels_data = pixels.into_iter().flatten().collect())
+                        Ok(PixelData::EightBit(pixels.into_iter().flatten().collect()))
                     }
                     ChannelInterpretation::RGB =>
                     {
@@ -215,7 +528,7 @@ impl StillPicture
                             .collect::<Vec<[u8; 3]>>();
                         lcms2::Transform::new(&self.icc, format, target, format, intent)
                             .map(|t| t.transform_in_place(&mut pixels))?;
-                        Ok(*pixels_data = pixels.into_iter().flatten().collect())
+                        Ok(PixelData::EightBit(pixels.into_iter().flatten().collect()))
                     }
                     ChannelInterpretation::RGBA =>
                     {
@@ -225,7 +538,7 @@ impl StillPicture
                             .collect::<Vec<[u8; 4]>>();
                         lcms2::Transform::new(&self.icc, format, target, format, intent)
                             .map(|t| t.transform_in_place(&mut pixels))?;
-                        Ok(*pixels_data = pixels.into_iter().flatten().collect())
+                        Ok(PixelData::EightBit(pixels.into_iter().flatten().collect()))
                     }
                 }
             }
@@ -239,7 +552,7 @@ impl StillPicture
                         let mut pixels = pixels_data.clone();
                         lcms2::Transform::new(&self.icc, format, target, format, intent)
                             .map(|t| t.transform_in_place(&mut pixels))?;
-                        Ok(*pixels_data = pixels.into_iter().collect())
+                        Ok(PixelData::SixteenBit(pixels))
                     }
                     ChannelInterpretation::LA =>
                     {
@@ -249,7 +562,7 @@ impl StillPicture
                             .collect::<Vec<[u16; 2]>>();
                         lcms2::Transform::new(&self.icc, format, target, format, intent)
                             .map(|t| t.transform_in_place(&mut pixels))?;
-                        Ok(*pixels_data = pixels.into_iter().flatten().collect())
+                        Ok(PixelData::SixteenBit(pixels.into_iter().flatten().collect()))
                     }
                     ChannelInterpretation::RGB =>
                     {
@@ -259,7 +572,7 @@ impl StillPicture
                             .collect::<Vec<[u16; 3]>>();
                         lcms2::Transform::new(&self.icc, format, target, format, intent)
                             .map(|t| t.transform_in_place(&mut pixels))?;
-                        Ok(*pixels_data = pixels.into_iter().flatten().collect())
+                        Ok(PixelData::SixteenBit(pixels.into_iter().flatten().collect()))
                     }
                     ChannelInterpretation::RGBA =>
                     {
@@ -269,27 +582,59 @@ impl StillPicture
                             .collect::<Vec<[u16; 4]>>();
                         lcms2::Transform::new(&self.icc, format, target, format, intent)
                             .map(|t| t.transform_in_place(&mut pixels))?;
-                        Ok(*pixels_data = pixels.into_iter().flatten().collect())
+                        Ok(PixelData::SixteenBit(pixels.into_iter().flatten().collect()))
                     }
                 }
             }
-        }
-    }
-
-    pub fn clone(&self) -> PictureResult<Self>
-    {
-        Ok
-        (
-            Self
+            PixelData::Float(pixels_data) =>
             {
-                pixel_data: self.pixel_data.clone(),
-                resolution: self.resolution,
-                channel_count: self.channel_count,
-                channel_interpretation: self.channel_interpretation,
-                gamma: self.gamma,
-                icc: lcms2::Profile::new_icc(&self.icc.icc()?)?
+                match self.channel_interpretation
+                {
+                    ChannelInterpretation::L =>
+                    {
+                        let format = PixelFormat::GRAY_FLT;
+                        let mut pixels = pixels_data.clone();
+                        lcms2::Transform::new(&self.icc, format, target, format, intent)
+                            .map(|t| t.transform_in_place(&mut pixels))?;
+                        Ok(PixelData::Float(pixels))
+                    }
+                    ChannelInterpretation::LA =>
+                    {
+                        let format = PixelFormat::GRAY_FLT;
+                        let mut pixels = pixels_data.chunks(2)
+                            .map(|c| c[0])
+                            .collect::<Vec<f32>>();
+                        lcms2::Transform::new(&self.icc, format, target, format, intent)
+                            .map(|t| t.transform_in_place(&mut pixels))?;
+                        let mut transformed = pixels_data.clone();
+                        transformed.chunks_mut(2)
+                            .zip(pixels)
+                            .for_each(|(c, l)| c[0] = l);
+                        Ok(PixelData::Float(transformed))
+                    }
+                    ChannelInterpretation::RGB =>
+                    {
+                        let format = PixelFormat::RGB_FLT;
+                        let mut pixels = pixels_data.chunks(3)
+                            .map(|c| [c[0], c[1], c[2]])
+                            .collect::<Vec<[f32; 3]>>();
+                        lcms2::Transform::new(&self.icc, format, target, format, intent)
+                            .map(|t| t.transform_in_place(&mut pixels))?;
+                        Ok(PixelData::Float(pixels.into_iter().flatten().collect()))
+                    }
+                    ChannelInterpretation::RGBA =>
+                    {
+                        let format = PixelFormat::RGBA_FLT;
+                        let mut pixels = pixels_data.chunks(4)
+                            .map(|c| [c[0], c[1], c[2], c[3]])
+                            .collect::<Vec<[f32; 4]>>();
+                        lcms2::Transform::new(&self.icc, format, target, format, intent)
+                            .map(|t| t.transform_in_place(&mut pixels))?;
+                        Ok(PixelData::Float(pixels.into_iter().flatten().collect()))
+                    }
+                }
             }
-        )
+        }
     }
 }
 
@@ -323,218 +668,853 @@ impl From<(lcms2::Profile, image::Frame)> for Frame
 
 // ------------------------------------------------------------
 
-pub struct FramesPlayer
+#[derive(Clone, Copy)]
+pub enum PlaybackControl
 {
-    frames: Vec<Frame>,
-    playhead: usize,
-    onset: Instant,
-    interval: Duration
+    TogglePause,
+    StepForward,
+    StepBackward,
+    SpeedUp,
+    SlowDown
 }
 
-impl FramesPlayer
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct PlaybackStatus
 {
-    fn new(frames: Vec<Frame>) -> PictureResult<Self>
-    {
-        Ok
-        (
-            Self
-            {
-                frames: match frames.len() == 0
-                {
-                    true => return Err(PictureError::ZeroFrames),
-                    false => frames
-                },
-                playhead: 0,
-                onset: Instant::now(),
-                interval: Duration::ZERO
-            }
-        )
-    }
+    pub frame: usize,
+    pub frame_count: Option<usize>,
+    pub speed: f32,
+    pub paused: bool
+}
 
-    pub fn next(&mut self) -> Option<&StillPicture>
+impl fmt::Display for PlaybackStatus
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
     {
-        if self.onset.elapsed() >= self.interval
+        write!(formatter, "Frame {}", self.frame + 1)?;
+        if let Some(count) = self.frame_count
         {
-            let entry = &self.frames[self.playhead];
-            self.playhead = (self.playhead + 1) % self.frames.len();
-            self.onset = Instant::now();
-            self.interval = entry.interval;
-            return Some(&entry.still)
+            write!(formatter, "/{count}")?
         }
-        None
+        write!(formatter, "  {}x", self.speed)?;
+        if self.paused
+        {
+            write!(formatter, "  Paused")?
+        }
+        Ok(())
     }
 }
 
 // ------------------------------------------------------------
 
-struct Newtype<T>(T); // E0119
+#[derive(Clone, Copy, PartialEq)]
+pub struct PageStatus
+{
+    pub page: usize,
+    pub page_count: usize
+}
 
-impl<A> TryFrom<(lcms2::Profile, Newtype<A>)> for FramesPlayer
-where
-    A: image::AnimationDecoder<'static>
+impl fmt::Display for PageStatus
 {
-    type Error = PictureError;
-    fn try_from((icc, decoder): (lcms2::Profile, Newtype<A>)) -> PictureResult<Self>
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
     {
-        let icc = icc.icc()?;
-        let frames = decoder.0.into_frames().map
-        (
-            move |result| result.map_err(PictureError::ImageError)
-                .map
-                (
-                    |frame| lcms2::Profile::new_icc(&icc)
-                        .map_err(PictureError::from)
-                        .map(|icc| Frame::from((icc, frame)))
-                )
-        ).flatten().collect::<Result<Vec<_>, _>>()?;
-        FramesPlayer::new(frames)
+        write!(formatter, "Page {}/{}", self.page + 1, self.page_count)
     }
 }
 
 // ------------------------------------------------------------
 
-pub enum Picture
+#[derive(Clone, Copy, PartialEq)]
+pub enum PictureStatus
 {
-    Still(StillPicture),
-    Motion(FramesPlayer)
+    Playback(PlaybackStatus),
+    Page(PageStatus)
 }
 
-impl<R> TryFrom<image::io::Reader<R>> for Picture
-where
-    R: io::Read + io::BufRead + io::Seek + 'static
+impl fmt::Display for PictureStatus
 {
-    type Error = PictureError;
-    fn try_from(reader: image::io::Reader<R>) -> PictureResult<Self>
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
     {
-        let format = reader.format().ok_or(PictureError::UnsupportedImageFormat)?;
-        let srgb = lcms2::Profile::new_srgb();
-        let this = match format
+        match self
         {
-            Png =>
-            {
-                let reader = reader.into_inner();
-                let mut decoder = png::PngDecoder::new(reader)
-                    .map_err(PictureError::ImageError)?;
-                let icc = decoder.icc_profile().map_or
-                (
-                    Ok(srgb),
-                    |icc| lcms2::Profile::new_icc(&icc)
-                )?;
-                match decoder.is_apng()
-                {
-                    false => Self::Still
-                    (
-                        image::DynamicImage::from_decoder(decoder)
-                            .map_err(PictureError::ImageError)
-                            .and_then(|d| StillPicture::try_from((icc, d)))?
-                    ),
-                    true =>
-                    {
-                        let decoder = Newtype(decoder.apng());
-                        let player = FramesPlayer::try_from((icc, decoder))?;
-                        Self::Motion(player)
-                    }
-                }
-            }
-            Jpeg =>
-            {
-                let reader = reader.into_inner();
-                let mut decoder = jpeg::JpegDecoder::new(reader)
-                    .map_err(PictureError::ImageError)?;
-                let icc = decoder.icc_profile().map_or
-                (
-                    Ok(srgb),
-                    |icc| lcms2::Profile::new_icc(&icc)
-                )?;
-                Self::Still
-                (
-                    image::DynamicImage::from_decoder(decoder)
-                        .map_err(PictureError::ImageError)
-                        .and_then(|d| StillPicture::try_from((icc, d)))?
-                )
-            }
-            Gif =>
-            {
-                let reader = reader.into_inner();
-                let decoder = gif::GifDecoder::new(reader)
-                    .map_err(PictureError::ImageError)?;
-                let decoder = Newtype(decoder);
-                let player = FramesPlayer::try_from((srgb, decoder))?;
-                Self::Motion(player)
-            }
-            OpenExr => Self::Still
-            (
-                reader.decode()
-                    .map_err(PictureError::ImageError)
-                    .and_then(|d| StillPicture::try_from((srgb, d)))
-                    .map
-                    (
-                        |mut s|
-                        {
-                            s.gamma = 1.0 / 2.2;
-                            s
-                        }
-                    )?
-            ),
-            _ => Self::Still
-            (
-                reader.decode()
-                    .map_err(PictureError::ImageError)
-                    .and_then(|d| StillPicture::try_from((srgb, d)))?
-            )
-        };
-        Ok(this)
+            Self::Playback(status) => status.fmt(formatter),
+            Self::Page(status) => status.fmt(formatter)
+        }
     }
 }
 
-pub fn open_picture(filepath: &std::path::Path) -> PictureResult<Picture>
+// ------------------------------------------------------------
+
+pub enum PictureFrame
 {
-    image::io::Reader::open(filepath).map_err(PictureError::IO)
-        .and_then(Picture::try_from)
+    Still(Rc<StillPicture>),
+    Decoded
+    {
+        index: usize,
+        still: StillPicture,
+        resident: bool
+    },
+    Resident(usize)
 }
 
 // ------------------------------------------------------------
 
-pub type PictureDimensions = [u32; 2];
-
-// ------------------------------------------------------------
+type FrameReceiver = Receiver<PictureResult<(usize, Frame)>>;
 
-pub fn read_dimensions<P: AsRef<std::path::Path>>(filepath: P)
-    -> PictureResult<PictureDimensions>
+#[derive(Clone, Copy, PartialEq)]
+enum Step
+{
+    Forward,
+    Seek
+}
+
+pub struct FramesPlayer
+{
+    spawn: Box<dyn Fn(usize, Option<u32>) -> FrameReceiver + Send>,
+    receiver: Option<FrameReceiver>,
+    pending: Option<PictureResult<(usize, Frame)>>,
+    stop: Arc<AtomicBool>,
+    waker: SharedWaker,
+    source_bytes: usize,
+    frame_bytes: usize,
+    stepped: bool,
+    step: Option<Step>,
+    intervals: Vec<Duration>,
+    resident_bytes: usize,
+    resident: bool,
+    plays: Option<u32>,
+    played: u32,
+    playhead: Option<usize>,
+    frame_count: Option<usize>,
+    onset: Instant,
+    interval: Duration,
+    speed: f32,
+    paused: Option<Duration>,
+    finished: bool
+}
+
+impl FramesPlayer
+{
+    const BUFFERED_FRAMES: usize = 8;
+    const RESIDENT_BUDGET: usize = 512 << 20;
+    const SPEED_RANGE: [f32; 2] = [0.125, 8.0];
+
+    pub fn next_frame(&mut self) -> Option<PictureResult<PictureFrame>>
+    {
+        if std::mem::take(&mut self.stepped)
+        {
+            return self.playhead.map(|playhead| Ok(PictureFrame::Resident(playhead)))
+        }
+        if self.step.is_some()
+        {
+            return self.take_step()
+        }
+        if self.paused.is_none() &&
+            self.onset.elapsed() >= self.interval.div_f32(self.speed)
+        {
+            return match self.receiver
+            {
+                Some(..) => self.advance(false),
+                None => self.replay_next()
+            }
+        }
+        None
+    }
+
+    pub fn control(&mut self, control: PlaybackControl)
+    {
+        match control
+        {
+            PlaybackControl::TogglePause => self.paused = match self.paused
+            {
+                Some(elapsed) =>
+                {
+                    self.onset = Instant::now() - elapsed;
+                    None
+                }
+                None => Some(self.onset.elapsed())
+            },
+            PlaybackControl::StepForward =>
+            {
+                match self.receiver
+                {
+                    Some(..) => self.step = self.step.or(Some(Step::Forward)),
+                    None =>
+                    {
+                        let next = self.playhead.map_or(0, |p| p + 1);
+                        self.replay(next % self.intervals.len());
+                        self.stepped = true
+                    }
+                }
+                self.paused = Some(Duration::ZERO)
+            }
+            PlaybackControl::StepBackward =>
+            {
+                let target = match self.playhead.unwrap_or(0)
+                {
+                    0 => self.frame_count.map(|count| count - 1),
+                    playhead => Some(playhead - 1)
+                };
+                if let Some(target) = target
+                {
+                    match self.receiver
+                    {
+                        Some(..) => self.seek(target),
+                        None =>
+                        {
+                            self.replay(target);
+                            self.stepped = true
+                        }
+                    }
+                }
+                self.paused = Some(Duration::ZERO)
+            }
+            PlaybackControl::SpeedUp => self.speed =
+                (self.speed * 2.0).min(Self::SPEED_RANGE[1]),
+            PlaybackControl::SlowDown => self.speed =
+                (self.speed * 0.5).max(Self::SPEED_RANGE[0])
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Instant>
+    {
+        if self.stepped
+        {
+            return Some(Instant::now())
+        }
+        if self.step.is_some() || self.paused.is_some() || self.finished
+        {
+            return None
+        }
+        let due = self.onset + self.interval.div_f32(self.speed);
+        match self.receiver
+        {
+            Some(..) if due <= Instant::now() => None,
+            _ => Some(due)
+        }
+    }
+
+    pub fn set_waker(&self, waker: Waker)
+    {
+        self.waker.set(waker)
+    }
+
+    pub fn byte_len(&self) -> usize
+    {
+        let buffered = match self.receiver
+        {
+            Some(..) => (Self::BUFFERED_FRAMES + 1) * self.frame_bytes,
+            None => 0
+        };
+        self.source_bytes + self.resident_bytes + buffered
+    }
+
+    pub fn into_first_frame(mut self) -> PictureResult<StillPicture>
+    {
+        match self.advance(true)
+        {
+            Some(Ok(PictureFrame::Decoded{still, ..})) => Ok(still),
+            Some(Err(error)) => Err(error),
+            _ => Err(PictureError::ZeroFrames)
+        }
+    }
+
+    pub fn status(&self) -> PlaybackStatus
+    {
+        PlaybackStatus
+        {
+            frame: self.playhead.unwrap_or(0),
+            frame_count: self.frame_count,
+            speed: self.speed,
+            paused: self.paused.is_some()
+        }
+    }
+
+    fn advance(&mut self, blocking: bool) -> Option<PictureResult<PictureFrame>>
+    {
+        let result = match self.pending.take()
+        {
+            Some(result) => result,
+            None =>
+            {
+                let receiver = self.receiver.as_ref()?;
+                let received = match blocking
+                {
+                    true => receiver.recv()
+                        .map_err(|_| TryRecvError::Disconnected),
+                    false => receiver.try_recv()
+                };
+                match received
+                {
+                    Ok(result) => result,
+                    Err(TryRecvError::Empty) => return None,
+                    Err(TryRecvError::Disconnected) =>
+                    {
+                        let last = self.playhead.unwrap_or(0);
+                        self.frame_count.get_or_insert(last + 1);
+                        self.settle();
+                        self.finished = true;
+                        return None
+                    }
+                }
+            }
+        };
+        Some
+        (
+            result.map
+            (
+                |(index, frame)|
+                {
+                    let seeking = self.step == Some(Step::Seek);
+                    if let Some(playhead) = self.playhead.filter(|p| !seeking && index <= *p)
+                    {
+                        self.frame_count = Some(playhead + 1);
+                        self.played += 1;
+                        if self.settle()
+                        {
+                            return self.replay(0)
+                        }
+                    }
+                    self.admit(index, frame)
+                }
+            )
+        )
+    }
+
+    fn settle(&mut self) -> bool
+    {
+        let complete = self.resident &&
+            self.frame_count == Some(self.intervals.len());
+        if complete
+        {
+            self.receiver = None;
+            self.pending = None
+        }
+        complete
+    }
+
+    fn take_step(&mut self) -> Option<PictureResult<PictureFrame>>
+    {
+        let stepped = self.advance(false);
+        if stepped.is_some()
+        {
+            self.step = None;
+            return stepped
+        }
+        if !self.finished
+        {
+            return None
+        }
+        match self.receiver
+        {
+            Some(..) =>
+            {
+                self.seek(0);
+                None
+            }
+            None =>
+            {
+                self.step = None;
+                Some(Ok(self.replay(0)))
+            }
+        }
+    }
+
+    fn seek(&mut self, index: usize)
+    {
+        let plays = self.plays
+            .map(|plays| plays.saturating_sub(self.played).max(1));
+        self.receiver = Some((self.spawn)(index, plays));
+        self.pending = None;
+        self.finished = false;
+        self.step = Some(Step::Seek)
+    }
+
+    fn replay_next(&mut self) -> Option<PictureResult<PictureFrame>>
+    {
+        let mut next = self.playhead.map_or(0, |p| p + 1);
+        if next >= self.intervals.len()
+        {
+            if self.plays.is_some_and(|plays| self.played + 1 >= plays)
+            {
+                self.finished = true;
+                return None
+            }
+            self.played += 1;
+            next = 0
+        }
+        Some(Ok(self.replay(next)))
+    }
+
+    fn replay(&mut self, index: usize) -> PictureFrame
+    {
+        self.playhead = Some(index);
+        self.onset = Instant::now();
+        self.interval = self.intervals[index];
+        self.finished = false;
+        PictureFrame::Resident(index)
+    }
+
+    fn admit(&mut self, index: usize, Frame{still, interval}: Frame) -> PictureFrame
+    {
+        if self.resident && index < self.intervals.len()
+        {
+            return self.replay(index)
+        }
+        let size = still.pixel_data.byte_len();
+        let resident = self.resident && index == self.intervals.len() &&
+            self.resident_bytes + size <= Self::RESIDENT_BUDGET;
+        match resident
+        {
+            true =>
+            {
+                self.intervals.push(interval);
+                self.resident_bytes += size
+            }
+            false => self.resident = false
+        }
+        self.playhead = Some(index);
+        self.onset = Instant::now();
+        self.interval = interval;
+        self.finished = false;
+        PictureFrame::Decoded{index, still, resident}
+    }
+}
+
+impl Drop for FramesPlayer
+{
+    fn drop(&mut self)
+    {
+        self.stop.store(true, Ordering::Relaxed)
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct SharedBytes(Arc<[u8]>);
+
+impl AsRef<[u8]> for SharedBytes
+{
+    fn as_ref(&self) -> &[u8]
+    {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for SharedBytes
+{
+    fn from(bytes: Vec<u8>) -> Self
+    {
+        Self(bytes.into())
+    }
+}
+
+pub type SharedReader = io::Cursor<SharedBytes>;
+
+// ------------------------------------------------------------
+
+pub struct AnimationSource<F>
+{
+    bytes: SharedBytes,
+    plays: Option<u32>,
+    open: F
+}
+
+impl<F> AnimationSource<F>
+{
+    pub fn new<R>
+    (
+        mut reader: R,
+        position: u64,
+        open: F,
+        play_count: fn(&[u8]) -> Option<u32>
+    ) -> PictureResult<Self>
+    where R: io::Read + io::Seek
+    {
+        let mut bytes = vec![];
+        reader.seek(io::SeekFrom::Start(position))
+            .and_then(|_| reader.read_to_end(&mut bytes))
+            .map_err(PictureError::IO)?;
+        let plays = play_count(&bytes);
+        Ok(Self{bytes: bytes.into(), plays, open})
+    }
+}
+
+impl<A, F> AnimationSource<F>
+where
+    A: image::AnimationDecoder<'static>,
+    F: Fn(SharedReader) -> image::ImageResult<A>
 {
-    image::image_dimensions(filepath)
-        .map(|(w, h)| [w, h])
-        .map_err(PictureError::ImageError)
+    fn produce
+    (
+        &self,
+        icc: &[u8],
+        mut skip: usize,
+        plays: Option<u32>,
+        stop: &AtomicBool,
+        waker: &SharedWaker,
+        sender: SyncSender<PictureResult<(usize, Frame)>>
+    )
+    {
+        let mut played = 0;
+        while plays.is_none_or(|plays| played < plays)
+        {
+            let reader = io::Cursor::new(self.bytes.clone());
+            let frames = match (self.open)(reader)
+            {
+                Ok(decoder) => decoder.into_frames(),
+                Err(error) => return
+                {
+                    let _ = sender.send(Err(PictureError::ImageError(error)));
+                }
+            };
+            let mut count = 0;
+            for (index, result) in frames.enumerate()
+            {
+                if stop.load(Ordering::Relaxed)
+                {
+                    return
+                }
+                count += 1;
+                if index < skip && result.is_ok()
+                {
+                    continue
+                }
+                let result = result.map_err(PictureError::ImageError)
+                    .and_then
+                    (
+                        |frame| lcms2::Profile::new_icc(icc)
+                            .map_err(PictureError::from)
+                            .map(|icc| (index, Frame::from((icc, frame))))
+                    );
+                let failed = result.is_err();
+                if sender.send(result).is_err() || failed
+                {
+                    return
+                }
+                waker.wake()
+            }
+            if count == 0
+            {
+                let _ = sender.send(Err(PictureError::ZeroFrames));
+                return
+            }
+            skip = 0;
+            played += 1
+        }
+    }
+}
+
+impl<A, F> TryFrom<(lcms2::Profile, AnimationSource<F>)> for FramesPlayer
+where
+    A: image::AnimationDecoder<'static>,
+    F: Fn(SharedReader) -> image::ImageResult<A> + Send + Sync + 'static
+{
+    type Error = PictureError;
+    fn try_from((icc, source): (lcms2::Profile, AnimationSource<F>)) -> PictureResult<Self>
+    {
+        let icc: Arc<[u8]> = icc.icc()?.into();
+        let plays = source.plays;
+        let source_bytes = source.bytes.as_ref().len();
+        let source = Arc::new(source);
+        let stop = Arc::new(AtomicBool::new(false));
+        let waker = SharedWaker::default();
+        let (stopped, woken) = (stop.clone(), waker.clone());
+        let spawn = move |skip, plays|
+        {
+            let (sender, receiver) = sync_channel(Self::BUFFERED_FRAMES);
+            let (source, icc) = (source.clone(), icc.clone());
+            let (stop, waker) = (stopped.clone(), woken.clone());
+            std::thread::spawn
+            (
+                move ||
+                {
+                    source.produce(&icc, skip, plays, &stop, &waker, sender);
+                    waker.wake()
+                }
+            );
+            receiver
+        };
+        let receiver = spawn(0, plays);
+        let pending = receiver.recv()
+            .map_err(|_| PictureError::ZeroFrames)??;
+        let frame_bytes = pending.1.still.pixel_data.byte_len();
+        Ok
+        (
+            Self
+            {
+                spawn: Box::new(spawn),
+                receiver: Some(receiver),
+                pending: Some(Ok(pending)),
+                stop,
+                waker,
+                source_bytes,
+                frame_bytes,
+                stepped: false,
+                step: None,
+                intervals: vec![],
+                resident_bytes: 0,
+                resident: true,
+                plays,
+                played: 0,
+                playhead: None,
+                frame_count: None,
+                onset: Instant::now(),
+                interval: Duration::ZERO,
+                speed: 1.0,
+                paused: None,
+                finished: false
+            }
+        )
+    }
 }
 
 // ------------------------------------------------------------
 
-pub fn extensions() -> Vec<&'static str>
-{
-    macro_rules! collect_extensions
-    {
-        ($($format:ident),+) =>
-        {{
-            let mut extensions: Vec<&str> = vec![];
-            $(extensions.extend($format.extensions_str());)+
-            extensions
-        }}
-    }
-    collect_extensions!
-    [
-        Png,
-        Jpeg,
-        Gif,
-        WebP,
-        Pnm,
-        Tiff,
-        Tga,
-        Dds,
-        Bmp,
-        Ico,
-        Hdr,
-        OpenExr,
-        Farbfeld,
-        Avif
-    ]
+pub type PageDecoder = Arc<dyn Fn(Vec<u8>) -> PictureResult<Picture> + Send + Sync>;
+
+pub struct PagedPicture
+{
+    bytes: SharedBytes,
+    decoder: PageDecoder,
+    pages: Vec<containers::SubImage>,
+    page: usize,
+    receiver: Option<Receiver<PictureResult<StillPicture>>>,
+    waker: SharedWaker
+}
+
+impl PagedPicture
+{
+    pub fn new
+    (
+        bytes: SharedBytes,
+        pages: Vec<containers::SubImage>,
+        decoder: PageDecoder
+    ) -> Self
+    {
+        let mut this = Self
+        {
+            bytes,
+            decoder,
+            pages,
+            page: 0,
+            receiver: None,
+            waker: SharedWaker::default()
+        };
+        this.decode();
+        this
+    }
+
+    pub fn next_page(&mut self) -> Option<PictureResult<Rc<StillPicture>>>
+    {
+        let result = self.receiver.as_ref()?.try_recv();
+        match result
+        {
+            Ok(result) =>
+            {
+                self.receiver = None;
+                Some(result.map(Rc::new))
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) =>
+            {
+                self.receiver = None;
+                None
+            }
+        }
+    }
+
+    pub fn turn(&mut self, offset: isize)
+    {
+        let page = self.page.saturating_add_signed(offset)
+            .min(self.pages.len() - 1);
+        if page != self.page
+        {
+            self.page = page;
+            self.decode()
+        }
+    }
+
+    pub fn set_waker(&self, waker: Waker)
+    {
+        self.waker.set(waker)
+    }
+
+    pub fn byte_len(&self) -> usize
+    {
+        self.bytes.as_ref().len()
+    }
+
+    pub fn status(&self) -> PageStatus
+    {
+        PageStatus{page: self.page, page_count: self.pages.len()}
+    }
+
+    pub fn into_page(self) -> PictureResult<StillPicture>
+    {
+        self.receiver
+            .ok_or(PictureError::UnsupportedImageFormat)?
+            .recv()
+            .map_err(|_| PictureError::UnsupportedImageFormat)?
+    }
+
+    fn decode(&mut self)
+    {
+        let (sender, receiver) = channel();
+        let (bytes, decoder, page) = (self.bytes.clone(), self.decoder.clone(), self.pages[self.page]);
+        let waker = self.waker.clone();
+        std::thread::spawn
+        (
+            move ||
+            {
+                let _ = sender.send
+                (
+                    page.extract(bytes.as_ref())
+                        .map_err(PictureError::IO)
+                        .and_then(|extracted| decoder(extracted))
+                        .and_then
+                        (
+                            |picture| match picture
+                            {
+                                Picture::Still(still) => Ok(still),
+                                _ => Err(PictureError::UnsupportedImageFormat)
+                            }
+                        )
+                );
+                waker.wake()
+            }
+        );
+        self.receiver = Some(receiver)
+    }
+}
+
+// ------------------------------------------------------------
+
+pub enum Picture
+{
+    Still(StillPicture),
+    Motion(FramesPlayer),
+    Paged(PagedPicture)
+}
+
+impl Picture
+{
+    pub fn into_still(self) -> PictureResult<StillPicture>
+    {
+        match self
+        {
+            Self::Still(still) => Ok(still),
+            Self::Motion(player) => player.into_first_frame(),
+            Self::Paged(paged) => paged.into_page()
+        }
+    }
+}
+
+pub struct CancellableReader<R>
+{
+    inner: R,
+    cancelled: Arc<AtomicBool>
+}
+
+impl<R> io::Read for CancellableReader<R>
+where R: io::Read
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        match self.cancelled.load(Ordering::Relaxed)
+        {
+            true => Err(io::Error::other("Decode cancelled")),
+            false => self.inner.read(buf)
+        }
+    }
+}
+
+impl<R> io::Seek for CancellableReader<R>
+where R: io::Seek
+{
+    fn seek(&mut self, position: io::SeekFrom) -> io::Result<u64>
+    {
+        self.inner.seek(position)
+    }
+}
+
+fn open_cancellable(filepath: &std::path::Path, cancelled: Arc<AtomicBool>)
+    -> io::Result<Box<dyn PictureRead>>
+{
+    match archives::read(filepath)?
+    {
+        Some(bytes) =>
+        {
+            let inner = io::Cursor::new(bytes);
+            Ok(Box::new(io::BufReader::new(CancellableReader{inner, cancelled})))
+        }
+        None => std::fs::File::open(filepath)
+            .map(|inner| Box::new(io::BufReader::new(CancellableReader{inner, cancelled})) as _)
+    }
+}
+
+pub fn open_file(filepath: &std::path::Path) -> io::Result<Box<dyn PictureRead>>
+{
+    open_cancellable(filepath, Default::default())
+}
+
+pub fn open_picture
+(
+    filepath: &std::path::Path,
+    cancelled: Arc<AtomicBool>,
+    partial: PartialSink
+) -> PictureResult<Picture>
+{
+    let mut partial = partial;
+    let mut reader = open_cancellable(filepath, cancelled).map_err(PictureError::IO)?;
+    DecoderRegistry::global()
+        .select(&mut reader, filepath)?
+        .decode(reader, &mut partial)
+}
+
+pub fn sniff_picture(filepath: &std::path::Path) -> bool
+{
+    use io::Read;
+    let mut magic = vec![];
+    open_file(filepath)
+        .and_then(|file| file.take(DecoderRegistry::MAGIC_SIZE as u64).read_to_end(&mut magic))
+        .is_ok_and(|_| DecoderRegistry::global().find(&magic, None).is_some())
+}
+
+pub fn open_preview(filepath: &std::path::Path) -> PictureResult<Option<StillPicture>>
+{
+    let mut reader = open_file(filepath).map_err(PictureError::IO)?;
+    DecoderRegistry::global()
+        .select(&mut reader, filepath)?
+        .preview(reader)
+}
+
+// ------------------------------------------------------------
+
+pub type PictureDimensions = [u32; 2];
+
+// ------------------------------------------------------------
+
+pub fn read_dimensions<P: AsRef<std::path::Path>>(filepath: P)
+    -> PictureResult<PictureDimensions>
+{
+    let filepath = filepath.as_ref();
+    let mut reader = open_file(filepath).map_err(PictureError::IO)?;
+    let orientation = Orientation::read(&mut reader);
+    io::Seek::rewind(&mut reader).map_err(PictureError::IO)?;
+    let ([w, h], bytes_per_pixel) = DecoderRegistry::global()
+        .select(&mut reader, filepath)?
+        .layout(&mut reader)?;
+    DecodeBudget::from_env().check([w, h], bytes_per_pixel)?;
+    Ok
+    (
+        match orientation.swaps_dimensions()
+        {
+            true => [h, w],
+            false => [w, h]
+        }
+    )
 }
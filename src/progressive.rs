@@ -0,0 +1,272 @@
+
+use
+{
+    std::{io, time::*},
+    super::picture::*,
+    image::{ImageBuffer, ImageFormat, DynamicImage::*}
+};
+
+// ------------------------------------------------------------
+
+pub struct PartialSink
+{
+    publish: Option<Box<dyn FnMut(StillPicture)>>,
+    published: Instant
+}
+
+impl PartialSink
+{
+    const INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new<F>(publish: F) -> Self
+    where F: FnMut(StillPicture) + 'static
+    {
+        Self{publish: Some(Box::new(publish)), published: Instant::now()}
+    }
+
+    pub fn none() -> Self
+    {
+        Self{publish: None, published: Instant::now()}
+    }
+
+    fn publish<F>(&mut self, snapshot: F)
+    where F: FnOnce() -> PictureResult<StillPicture>
+    {
+        if let Some(publish) = self.publish.as_mut()
+            .filter(|_| self.published.elapsed() >= Self::INTERVAL)
+        {
+            if let Ok(still) = snapshot()
+            {
+                publish(still)
+            }
+            self.published = Instant::now()
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+fn decoding_error<E>(format: ImageFormat) -> impl Fn(E) -> PictureError
+where E: Into<Box<dyn std::error::Error + Send + Sync>>
+{
+    move |error| PictureError::ImageError
+    (
+        image::ImageError::Decoding(image::error::DecodingError::new(format.into(), error))
+    )
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+struct Pass
+{
+    origin: [usize; 2],
+    step: [usize; 2],
+    block: [usize; 2]
+}
+
+impl Pass
+{
+    const SEQUENTIAL: [Self; 1] =
+    [
+        Self{origin: [0, 0], step: [1, 1], block: [1, 1]}
+    ];
+
+    const ADAM7: [Self; 7] =
+    [
+        Self{origin: [0, 0], step: [8, 8], block: [8, 8]},
+        Self{origin: [4, 0], step: [8, 8], block: [4, 8]},
+        Self{origin: [0, 4], step: [4, 8], block: [4, 4]},
+        Self{origin: [2, 0], step: [4, 4], block: [2, 4]},
+        Self{origin: [0, 2], step: [2, 4], block: [2, 2]},
+        Self{origin: [1, 0], step: [2, 2], block: [1, 2]},
+        Self{origin: [0, 1], step: [1, 2], block: [1, 1]}
+    ];
+
+    fn extent(&self, [width, height]: [usize; 2]) -> [usize; 2]
+    {
+        let extent = |size: usize, axis: usize| size.saturating_sub(self.origin[axis])
+            .div_ceil(self.step[axis]);
+        match (extent(width, 0), extent(height, 1))
+        {
+            (0, _) | (_, 0) => [0, 0],
+            (columns, lines) => [columns, lines]
+        }
+    }
+}
+
+fn png_image
+(
+    (color_type, bit_depth): (png::ColorType, png::BitDepth),
+    [width, height]: [usize; 2],
+    buffer: Vec<u8>
+) -> PictureResult<image::DynamicImage>
+{
+    use png::{ColorType::*, BitDepth::*};
+    let wide = |buffer: Vec<u8>| buffer
+        .chunks_exact(2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .collect::<Vec<_>>();
+    let (width, height) = (width as u32, height as u32);
+    match (color_type, bit_depth)
+    {
+        (Grayscale, Eight) => ImageBuffer::from_raw(width, height, buffer).map(ImageLuma8),
+        (GrayscaleAlpha, Eight) => ImageBuffer::from_raw(width, height, buffer).map(ImageLumaA8),
+        (Rgb, Eight) => ImageBuffer::from_raw(width, height, buffer).map(ImageRgb8),
+        (Rgba, Eight) => ImageBuffer::from_raw(width, height, buffer).map(ImageRgba8),
+        (Grayscale, Sixteen) => ImageBuffer::from_raw(width, height, wide(buffer)).map(ImageLuma16),
+        (GrayscaleAlpha, Sixteen) => ImageBuffer::from_raw(width, height, wide(buffer)).map(ImageLumaA16),
+        (Rgb, Sixteen) => ImageBuffer::from_raw(width, height, wide(buffer)).map(ImageRgb16),
+        (Rgba, Sixteen) => ImageBuffer::from_raw(width, height, wide(buffer)).map(ImageRgba16),
+        _ => None
+    }.ok_or(PictureError::UnsupportedPixelFormat)
+}
+
+pub fn decode_png<R, S>
+(
+    reader: R,
+    budget: &DecodeBudget,
+    partial: &mut PartialSink,
+    snapshot: S
+) -> PictureResult<image::DynamicImage>
+where
+    R: io::Read,
+    S: Fn(image::DynamicImage) -> PictureResult<StillPicture>
+{
+    let error = decoding_error(ImageFormat::Png);
+    let limits = png::Limits{bytes: budget.bytes.try_into().unwrap_or(usize::MAX)};
+    let mut decoder = png::Decoder::new_with_limits(reader, limits);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(&error)?;
+    let output = reader.output_color_type();
+    let info = reader.info();
+    let size = [info.width as usize, info.height as usize];
+    let passes = match info.interlaced
+    {
+        true => &Pass::ADAM7[..],
+        false => &Pass::SEQUENTIAL[..]
+    };
+    let pixel_size = output.0.samples() * output.1 as usize / 8;
+    let stride = size[0] * pixel_size;
+    let mut buffer = vec![0; stride * size[1]];
+    let mut rows = passes.iter()
+        .flat_map(|pass| (0..pass.extent(size)[1]).map(move |line| (*pass, line)));
+    while let Some(row) = reader.next_interlaced_row().map_err(&error)?
+    {
+        let (pass, line) = rows.next().ok_or(PictureError::UnsupportedPixelFormat)?;
+        let y = pass.origin[1] + line * pass.step[1];
+        for (column, pixel) in row.data().chunks_exact(pixel_size).enumerate()
+        {
+            let x = pass.origin[0] + column * pass.step[0];
+            for block_y in y..(y + pass.block[1]).min(size[1])
+            {
+                let start = block_y * stride + x * pixel_size;
+                let end = block_y * stride + (x + pass.block[0]).min(size[0]) * pixel_size;
+                for target in buffer[start..end].chunks_exact_mut(pixel_size)
+                {
+                    target.copy_from_slice(pixel)
+                }
+            }
+        }
+        partial.publish(|| png_image(output, size, buffer.clone()).and_then(&snapshot))
+    }
+    png_image(output, size, buffer)
+}
+
+// ------------------------------------------------------------
+
+fn tiff_image
+(
+    channels: usize,
+    [width, height]: [u32; 2],
+    result: tiff::decoder::DecodingResult
+) -> PictureResult<image::DynamicImage>
+{
+    use tiff::decoder::DecodingResult::*;
+    match (channels, result)
+    {
+        (1, U8(data)) => ImageBuffer::from_raw(width, height, data).map(ImageLuma8),
+        (2, U8(data)) => ImageBuffer::from_raw(width, height, data).map(ImageLumaA8),
+        (3, U8(data)) => ImageBuffer::from_raw(width, height, data).map(ImageRgb8),
+        (4, U8(data)) => ImageBuffer::from_raw(width, height, data).map(ImageRgba8),
+        (1, U16(data)) => ImageBuffer::from_raw(width, height, data).map(ImageLuma16),
+        (2, U16(data)) => ImageBuffer::from_raw(width, height, data).map(ImageLumaA16),
+        (3, U16(data)) => ImageBuffer::from_raw(width, height, data).map(ImageRgb16),
+        (4, U16(data)) => ImageBuffer::from_raw(width, height, data).map(ImageRgba16),
+        _ => None
+    }.ok_or(PictureError::UnsupportedPixelFormat)
+}
+
+pub fn decode_tiff<R, S>
+(
+    reader: R,
+    budget: &DecodeBudget,
+    partial: &mut PartialSink,
+    snapshot: S
+) -> PictureResult<Option<image::DynamicImage>>
+where
+    R: io::Read + io::Seek,
+    S: Fn(image::DynamicImage) -> PictureResult<StillPicture>
+{
+    use tiff::{ColorType::*, decoder::{DecodingResult, Limits}, tags::Tag};
+    const PLANAR_SEPARATE: u16 = 2;
+    let error = decoding_error(ImageFormat::Tiff);
+    let mut limits = Limits::default();
+    limits.decoding_buffer_size = budget.bytes.try_into().unwrap_or(usize::MAX);
+    limits.intermediate_buffer_size = limits.decoding_buffer_size;
+    let mut decoder = tiff::decoder::Decoder::new(reader)
+        .map_err(&error)?
+        .with_limits(limits);
+    let (width, height) = decoder.dimensions().map_err(&error)?;
+    let (channels, wide) = match decoder.colortype().map_err(&error)?
+    {
+        Gray(8) => (1, false),
+        GrayA(8) => (2, false),
+        RGB(8) => (3, false),
+        RGBA(8) => (4, false),
+        Gray(16) => (1, true),
+        GrayA(16) => (2, true),
+        RGB(16) => (3, true),
+        RGBA(16) => (4, true),
+        _ => return Ok(None)
+    };
+    let planar = decoder.find_tag_unsigned::<u16>(Tag::PlanarConfiguration)
+        .map_err(&error)?;
+    let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+    let (chunk_width, chunk_height) = (chunk_width.min(width), chunk_height.min(height));
+    if planar == Some(PLANAR_SEPARATE) || chunk_width == 0 || chunk_height == 0
+    {
+        return Ok(None)
+    }
+    budget.check([width, height], (channels * if wide {2} else {1}) as u64)?;
+    let samples = width as usize * height as usize * channels;
+    let mut result = match wide
+    {
+        false => DecodingResult::U8(vec![0; samples]),
+        true => DecodingResult::U16(vec![0; samples])
+    };
+    let chunks_across = ((width - 1) / chunk_width + 1) as usize;
+    let chunks_down = ((height - 1) / chunk_height + 1) as usize;
+    let strip_samples = width as usize * chunk_height as usize * channels;
+    for chunk in 0..chunks_across * chunks_down
+    {
+        let offset = (chunk / chunks_across) * strip_samples
+            + (chunk % chunks_across) * chunk_width as usize * channels;
+        decoder.read_chunk_to_buffer(result.as_buffer(offset), chunk as u32, width as usize)
+            .map_err(&error)?;
+        partial.publish
+        (
+            ||
+            {
+                let copy = match &result
+                {
+                    DecodingResult::U8(data) => DecodingResult::U8(data.clone()),
+                    DecodingResult::U16(data) => DecodingResult::U16(data.clone()),
+                    _ => return Err(PictureError::UnsupportedPixelFormat)
+                };
+                tiff_image(channels, [width, height], copy).and_then(&snapshot)
+            }
+        )
+    }
+    tiff_image(channels, [width, height], result).map(Some)
+}
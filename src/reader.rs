@@ -4,6 +4,7 @@ use
     std::path::*,
     super::
     {
+        utility::Waker,
         picture::*,
         loader::*,
         navigator::*
@@ -15,42 +16,98 @@ use
 pub struct PictureDirectoryReader
 {
     navigator: FilepathsNavigator,
-    loader: PictureLoader
+    loader: PictureLoader,
+    options: NavigatorOptions,
+    waker: Waker
 }
 
 impl PictureDirectoryReader
 {
-    pub fn new<P: AsRef<Path>>(path: P) -> NavigatorResult<Self>
+    pub fn new
+    (
+        source: FilepathsSource,
+        options: NavigatorOptions,
+        waker: Waker
+    ) -> NavigatorResult<Self>
     {
-        FilepathsNavigator::from_path(path, &extensions()).map
+        FilepathsNavigator::from_source(source, &options, waker.clone()).map
         (
             |navigator|
             {
-                let mut loader = PictureLoader::new();
-                loader.load(navigator.selected());
+                let mut loader = PictureLoader::new(waker.clone());
+                loader.load(navigator.selected(), &navigator.neighbors());
                 Self
                 {
                     navigator,
-                    loader
+                    loader,
+                    options,
+                    waker
                 }
             }
         )
     }
 
-    pub fn change_path<P>(mut self, path: P) -> NavigatorResult<Self>
-    where P: AsRef<Path>
+    pub fn change_source(mut self, source: FilepathsSource) -> NavigatorResult<Self>
     {
-        FilepathsNavigator::from_path(path, &extensions()).map
+        FilepathsNavigator::from_source(source, &self.options, self.waker.clone()).map
         (
             |navigator|
             {
-                self.loader.load(navigator.selected());
+                self.loader.load(navigator.selected(), &navigator.neighbors());
                 self.navigator = navigator;
                 self
             }
         )
     }
 
+    pub fn change_directory(&mut self, direction: i8) -> NavigatorResult<bool>
+    {
+        let directory = match self.navigator.sibling_directory(direction)?
+        {
+            Some(directory) => directory,
+            None => return Ok(false)
+        };
+        let mut navigator = FilepathsNavigator::from_source
+        (
+            FilepathsSource::Path(directory),
+            &self.options,
+            self.waker.clone()
+        )?;
+        if direction < 0
+        {
+            navigator.navigate(-1)
+        }
+        self.loader.load(navigator.selected(), &navigator.neighbors());
+        self.navigator = navigator;
+        Ok(true)
+    }
+
+    pub fn set_options(mut self, options: NavigatorOptions) -> NavigatorResult<Self>
+    {
+        if let Ok(navigator) = self.navigator.reopen(&options, self.waker.clone())
+        {
+            self.loader.load(navigator.selected(), &navigator.neighbors());
+            self.navigator = navigator;
+            self.options = options;
+            return Ok(self)
+        }
+        let selected = self.selected_filepath();
+        let path = match options.accepts(selected)
+        {
+            true => selected.clone(),
+            false => selected.parent()
+                .map_or_else(|| selected.clone(), Path::to_path_buf)
+        };
+        self.options = options;
+        self.change_source(FilepathsSource::Path(path))
+    }
+
+    pub fn sort(&mut self, order: SortOrder)
+    {
+        self.options.order = order;
+        self.navigator.sort(order)
+    }
+
     pub fn selected_filepath(&self) -> &PathBuf
     {
         self.navigator.selected()
@@ -62,7 +119,7 @@ impl PictureDirectoryReader
         self.navigator = navigator;
         if dirty
         {
-            self.loader.load(self.navigator.selected())
+            self.loader.load(self.navigator.selected(), &self.navigator.neighbors())
         }
         Ok(self)
     }
@@ -70,7 +127,27 @@ impl PictureDirectoryReader
     pub fn navigate(&mut self, direction: i8) -> ()
     {
         self.navigator.navigate(direction);
-        self.loader.load(self.navigator.selected())
+        self.loader.load(self.navigator.selected(), &self.navigator.neighbors())
+    }
+
+    pub fn control_playback(&mut self, control: PlaybackControl)
+    {
+        self.loader.control_playback(control)
+    }
+
+    pub fn turn_page(&mut self, offset: isize)
+    {
+        self.loader.turn_page(offset)
+    }
+
+    pub fn picture_status(&self) -> Option<PictureStatus>
+    {
+        self.loader.picture_status()
+    }
+
+    pub fn deadline(&self) -> Option<std::time::Instant>
+    {
+        self.loader.deadline()
     }
 }
 
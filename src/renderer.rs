@@ -110,6 +110,86 @@ impl ErrorPainter
 
 // ------------------------------------------------------------
 
+struct CaptionPainter
+{
+    filler: Filler,
+    typewriter: Typewriter,
+    caption: Option<String>,
+    scale_factor: f32
+}
+
+impl CaptionPainter
+{
+    const FONT_SIZE: f32 = 12.0;
+    const MARGIN: f32 = 6.0;
+
+    fn new(pointers: &FunctionPointers) -> Self
+    {
+        Self
+        {
+            filler: Filler::new(pointers),
+            typewriter: Typewriter::new
+            (
+                pointers,
+                FONT,
+                Self::FONT_SIZE as _
+            ),
+            caption: None,
+            scale_factor: 1.0
+        }
+    }
+
+    fn set_caption(&mut self, caption: Option<String>)
+    {
+        if let Some(caption) = &caption
+        {
+            self.typewriter.layout_text(caption, 80)
+        }
+        self.caption = caption
+    }
+
+    fn set_scale_factor(&mut self, scale_factor: f32)
+    {
+        self.typewriter.change_font_size
+        (
+            (Self::FONT_SIZE * scale_factor).round() as _
+        );
+        self.scale_factor = scale_factor
+    }
+
+    fn draw(&mut self, viewport: &GLViewport)
+    {
+        if self.caption.is_none()
+        {
+            return
+        }
+        let margin = (Self::MARGIN * self.scale_factor).round() as i32;
+        let dimensions = self.typewriter.dimensions();
+        self.filler.fill
+        (
+            [1.0, 1.0, 1.0, 0.75],
+            &GLViewport
+            {
+                origin: viewport.origin,
+                size:
+                [
+                    dimensions[0] + 2 * margin as u32,
+                    dimensions[1] + 2 * margin as u32
+                ]
+            }
+        );
+        self.typewriter.draw
+        (
+            [
+                viewport.origin[0] + margin,
+                viewport.origin[1] + margin
+            ]
+        )
+    }
+}
+
+// ------------------------------------------------------------
+
 struct BlankPainter(Filler);
 
 impl BlankPainter
@@ -140,11 +220,18 @@ impl PicturePainter
         Self(Blitter::new(pointers))
     }
     
-    fn set_picture(&mut self, still: &picture::StillPicture) -> ()
+    fn set_picture
+    (
+        &mut self,
+        still: &picture::StillPicture,
+        slot: Option<usize>
+    )
     {
+        let channel_order = still.channel_interpretation
+            .swizzle_for_rgba();
         match &still.pixel_data
         {
-            picture::PixelData::EightBit(data) => self.0.upload_texture
+            picture::PixelData::EightBit(data) => self.upload
             (
                 Image::<u8>
                 {
@@ -152,11 +239,11 @@ impl PicturePainter
                     resolution: still.resolution,
                     channel_count: still.channel_count
                 },
-                still.channel_interpretation
-                    .swizzle_for_rgba(),
-                still.gamma
+                channel_order,
+                still.gamma,
+                slot
             ),
-            picture::PixelData::SixteenBit(data) => self.0.upload_texture
+            picture::PixelData::SixteenBit(data) => self.upload
             (
                 Image::<u16>
                 {
@@ -164,13 +251,63 @@ impl PicturePainter
                     resolution: still.resolution, 
                     channel_count: still.channel_count
                 },
-                still.channel_interpretation
-                    .swizzle_for_rgba(),
-                still.gamma
+                channel_order,
+                still.gamma,
+                slot
+            ),
+            picture::PixelData::Float(data) => self.upload
+            (
+                Image::<f32>
+                {
+                    data: Some(data),
+                    resolution: still.resolution,
+                    channel_count: still.channel_count
+                },
+                channel_order,
+                still.gamma,
+                slot
             )
         }
     }
 
+    fn upload<T: TextureBaseDataType>
+    (
+        &mut self,
+        image: Image<T>,
+        channel_order: [i32; 4],
+        gamma: f32,
+        slot: Option<usize>
+    )
+    {
+        match slot
+        {
+            Some(index) => self.0
+                .upload_resident_texture(index, image, channel_order, gamma),
+            None => self.0
+                .upload_texture(image, channel_order, gamma)
+        }
+    }
+
+    fn select_resident(&mut self, index: usize) -> bool
+    {
+        self.0.select_resident_texture(index)
+    }
+
+    fn release_resident(&mut self)
+    {
+        self.0.release_resident_textures()
+    }
+
+    fn set_tone(&mut self, tone: &Tone)
+    {
+        self.0.set_tone(tone)
+    }
+
+    fn is_high_dynamic_range(&self) -> bool
+    {
+        self.0.is_high_dynamic_range()
+    }
+
     fn draw(&mut self, viewport: &GLViewport) -> ()
     {
         self.0.blit(viewport)
@@ -193,6 +330,9 @@ struct Renderer
     blank: BlankPainter,
     picture: PicturePainter,
     error: ErrorPainter,
+    caption: CaptionPainter,
+    tone: Tone,
+    status: Option<picture::PictureStatus>,
     mode: RenderMode
 }
 
@@ -205,28 +345,72 @@ impl Renderer
             blank: BlankPainter::new(pointers),
             picture: PicturePainter::new(pointers),
             error: ErrorPainter::new(pointers),
+            caption: CaptionPainter::new(pointers),
+            tone: Default::default(),
+            status: None,
             mode: RenderMode::Blank
         }
     }
     
     fn set_scale_factor(&mut self, scale_factor: f32) -> ()
     {
-        self.error.set_scale_factor(scale_factor)
+        self.error.set_scale_factor(scale_factor);
+        self.caption.set_scale_factor(scale_factor)
+    }
+
+    fn set_tone(&mut self, tone: &Tone)
+    {
+        self.tone = *tone;
+        self.picture.set_tone(tone);
+        self.refresh_caption()
+    }
+
+    fn set_picture_status(&mut self, status: Option<picture::PictureStatus>)
+    {
+        self.status = status;
+        self.refresh_caption()
+    }
+
+    fn refresh_caption(&mut self)
+    {
+        let tone = self.picture.is_high_dynamic_range()
+            .then(|| self.tone.to_string());
+        let status = self.status.map(|s| s.to_string());
+        let caption = [tone, status].into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        self.caption.set_caption
+        (
+            (!caption.is_empty()).then(|| caption.join("  |  "))
+        )
     }
 
     fn use_blank_mode(&mut self) -> ()
     {
-        self.mode = RenderMode::Blank
+        self.mode = RenderMode::Blank;
+        self.picture.release_resident()
     }
 
     fn use_picture_mode
     (
         &mut self,
-        still: &picture::StillPicture
+        still: &picture::StillPicture,
+        slot: Option<usize>
     ) -> ()
     {
         self.mode = RenderMode::Picture;
-        self.picture.set_picture(still)
+        self.picture.set_picture(still, slot);
+        self.refresh_caption()
+    }
+
+    fn use_resident_picture_mode(&mut self, index: usize) -> bool
+    {
+        let available = self.picture.select_resident(index);
+        if available
+        {
+            self.mode = RenderMode::Picture
+        }
+        available
     }
 
     fn use_error_mode<E>(&mut self, error: &E) -> ()
@@ -246,7 +430,11 @@ impl Renderer
         match &self.mode
         {
             RenderMode::Blank => self.blank.draw(viewport),
-            RenderMode::Picture => self.picture.draw(viewport),
+            RenderMode::Picture =>
+            {
+                self.picture.draw(viewport);
+                self.caption.draw(viewport)
+            }
             RenderMode::Error => self.error.draw()
         }
     }
@@ -586,13 +774,36 @@ impl RenderWindow
 
     pub fn use_blank_mode(&mut self) -> ()
     {
+        self.window.make_context_current();
         self.renderer.use_blank_mode()
     }
 
-    pub fn use_picture_mode(&mut self, still: &picture::StillPicture) -> ()
+    pub fn use_picture_mode
+    (
+        &mut self,
+        still: &picture::StillPicture,
+        slot: Option<usize>
+    )
     {
         self.window.make_context_current();
-        self.renderer.use_picture_mode(still)
+        self.renderer.use_picture_mode(still, slot)
+    }
+
+    pub fn use_resident_picture_mode(&mut self, index: usize) -> bool
+    {
+        self.window.make_context_current();
+        self.renderer.use_resident_picture_mode(index)
+    }
+
+    pub fn set_tone(&mut self, tone: &Tone)
+    {
+        self.window.make_context_current();
+        self.renderer.set_tone(tone)
+    }
+
+    pub fn set_picture_status(&mut self, status: Option<picture::PictureStatus>)
+    {
+        self.renderer.set_picture_status(status)
     }
     
     pub fn use_error_mode<E>(&mut self, error: &E) -> ()
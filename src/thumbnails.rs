@@ -0,0 +1,430 @@
+
+use
+{
+    std::{io, fs, path::*, time::UNIX_EPOCH},
+    image::{ImageBuffer, DynamicImage::*},
+    super::{picture::*, painters::Tone, progressive::PartialSink}
+};
+
+// ------------------------------------------------------------
+
+fn md5(message: &[u8]) -> [u8; 16]
+{
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+    const CONSTANTS: [u32; 64] =
+    [
+        0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee,
+        0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+        0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be,
+        0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+        0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa,
+        0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+        0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+        0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+        0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c,
+        0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+        0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05,
+        0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+        0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039,
+        0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+        0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1,
+        0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391
+    ];
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    padded.resize((padded.len() + 8).next_multiple_of(64) - 8, 0);
+    padded.extend(((message.len() as u64) << 3).to_le_bytes());
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for block in padded.chunks_exact(64)
+    {
+        let words: Vec<u32> = block.chunks_exact(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64
+        {
+            let (f, g) = match i / 16
+            {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16)
+            };
+            let rotated = a.wrapping_add(f)
+                .wrapping_add(CONSTANTS[i])
+                .wrapping_add(words[g])
+                .rotate_left(SHIFTS[(i / 16) * 4 + i % 4]);
+            (a, b, c, d) = (d, b.wrapping_add(rotated), b, c);
+        }
+        for (word, value) in state.iter_mut().zip([a, b, c, d])
+        {
+            *word = word.wrapping_add(value)
+        }
+    }
+    let mut digest = [0; 16];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state)
+    {
+        bytes.copy_from_slice(&word.to_le_bytes())
+    }
+    digest
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug)]
+pub enum ThumbnailSize
+{
+    Normal,
+    Large,
+    XLarge,
+    XXLarge
+}
+
+impl ThumbnailSize
+{
+    pub fn pixels(&self) -> u32
+    {
+        match self
+        {
+            Self::Normal => 128,
+            Self::Large => 256,
+            Self::XLarge => 512,
+            Self::XXLarge => 1024
+        }
+    }
+
+    fn directory(&self) -> &'static str
+    {
+        match self
+        {
+            Self::Normal => "normal",
+            Self::Large => "large",
+            Self::XLarge => "x-large",
+            Self::XXLarge => "xx-large"
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+struct Source
+{
+    uri: String,
+    modified: u64,
+    size: u64
+}
+
+impl Source
+{
+    fn read(filepath: &Path) -> io::Result<Self>
+    {
+        let metadata = fs::metadata(filepath)?;
+        let modified = metadata.modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Ok(Self{uri: Self::uri(filepath)?, modified, size: metadata.len()})
+    }
+
+    fn uri(filepath: &Path) -> io::Result<String>
+    {
+        const UNRESERVED: &[u8] = b"!$&'()*+,-./:=@_~";
+        let path = std::env::current_dir()?.join(filepath);
+        #[cfg(unix)]
+        let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+        #[cfg(not(unix))]
+        let bytes = path.to_string_lossy().replace('\\', "/").into_bytes();
+        let mut uri = String::from("file://");
+        if bytes.first() != Some(&b'/')
+        {
+            uri.push('/')
+        }
+        for byte in bytes
+        {
+            match byte.is_ascii_alphanumeric() || UNRESERVED.contains(&byte)
+            {
+                true => uri.push(byte as char),
+                false => uri.push_str(&format!("%{byte:02X}"))
+            }
+        }
+        Ok(uri)
+    }
+
+    fn key(&self) -> String
+    {
+        md5(self.uri.as_bytes()).iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+pub fn filepath_from_uri(uri: &str) -> Option<PathBuf>
+{
+    let path = uri.strip_prefix("file://")?;
+    let path = &path[path.find('/')?..];
+    let mut bytes = vec![];
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first()
+    {
+        rest = match byte
+        {
+            b'%' =>
+            {
+                let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                &tail[2..]
+            }
+            _ =>
+            {
+                bytes.push(byte);
+                tail
+            }
+        }
+    }
+    #[cfg(unix)]
+    let path = PathBuf::from(<std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(&bytes));
+    #[cfg(not(unix))]
+    let path = PathBuf::from(String::from_utf8(bytes).ok()?.trim_start_matches('/'));
+    Some(path)
+}
+
+// ------------------------------------------------------------
+
+fn into_dynamic_image(still: StillPicture, tone: &Tone) -> PictureResult<image::DynamicImage>
+{
+    use {PixelData::*, ChannelInterpretation::*};
+    let [width, height] = still.resolution;
+    let gamma = still.gamma;
+    let encode = |data: Vec<f32>, channels: usize| data.into_iter()
+        .enumerate()
+        .map(|(i, value)| match i % channels
+        {
+            3 => value,
+            _ => tone.apply(value).powf(gamma)
+        })
+        .collect::<Vec<_>>();
+    match (still.pixel_data, still.channel_interpretation)
+    {
+        (EightBit(data), L) => ImageBuffer::from_raw(width, height, data).map(ImageLuma8),
+        (EightBit(data), LA) => ImageBuffer::from_raw(width, height, data).map(ImageLumaA8),
+        (EightBit(data), RGB) => ImageBuffer::from_raw(width, height, data).map(ImageRgb8),
+        (EightBit(data), RGBA) => ImageBuffer::from_raw(width, height, data).map(ImageRgba8),
+        (SixteenBit(data), L) => ImageBuffer::from_raw(width, height, data).map(ImageLuma16),
+        (SixteenBit(data), LA) => ImageBuffer::from_raw(width, height, data).map(ImageLumaA16),
+        (SixteenBit(data), RGB) => ImageBuffer::from_raw(width, height, data).map(ImageRgb16),
+        (SixteenBit(data), RGBA) => ImageBuffer::from_raw(width, height, data).map(ImageRgba16),
+        (Float(data), RGB) => ImageBuffer::from_raw(width, height, encode(data, 3)).map(ImageRgb32F),
+        (Float(data), RGBA) => ImageBuffer::from_raw(width, height, encode(data, 4)).map(ImageRgba32F),
+        _ => None
+    }.ok_or(PictureError::UnsupportedPixelFormat)
+}
+
+fn scale_thumbnail(still: &StillPicture, pixels: u32) -> PictureResult<image::RgbaImage>
+{
+    let still = still.to_icc(&lcms2::Profile::new_srgb())?;
+    let dynamic_image = into_dynamic_image(still, &Tone::default())?;
+    let dynamic_image = match dynamic_image.width().max(dynamic_image.height()) > pixels
+    {
+        true => dynamic_image.thumbnail(pixels, pixels),
+        false => dynamic_image
+    };
+    Ok(dynamic_image.into_rgba8())
+}
+
+pub fn generate_thumbnail(filepath: &Path, pixels: u32)
+    -> PictureResult<(image::RgbaImage, PictureDimensions)>
+{
+    let dimensions = read_dimensions(filepath)?;
+    let still = match open_preview(filepath)?
+    {
+        Some(preview) => preview,
+        None => open_picture(filepath, Default::default(), PartialSink::none())?.into_still()?
+    };
+    Ok((scale_thumbnail(&still, pixels)?, dimensions))
+}
+
+pub fn save_thumbnail
+(
+    output: &Path,
+    filepath: &Path,
+    thumbnail: &image::RgbaImage,
+    dimensions: PictureDimensions
+) -> io::Result<()>
+{
+    let source = Source::read(filepath)?;
+    ThumbnailCache::write(output, &source, thumbnail, dimensions)
+}
+
+// ------------------------------------------------------------
+
+pub struct ThumbnailCache
+{
+    root: PathBuf
+}
+
+impl ThumbnailCache
+{
+    const SOFTWARE: &'static str = concat!("ochra ", env!("CARGO_PKG_VERSION"));
+
+    pub fn new() -> Option<Self>
+    {
+        let cache = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+        Some(Self{root: cache.join("thumbnails")})
+    }
+
+    fn entry(&self, directory: &str, source: &Source) -> PathBuf
+    {
+        self.root.join(directory).join(source.key() + ".png")
+    }
+
+    pub fn find(&self, filepath: &Path, size: ThumbnailSize) -> Option<StillPicture>
+    {
+        let source = Source::read(filepath).ok()?;
+        Self::read(&self.entry(size.directory(), &source), &source).ok()
+    }
+
+    pub fn contains(&self, filepath: &Path, size: ThumbnailSize) -> bool
+    {
+        Source::read(filepath).is_ok_and
+        (
+            |source| fs::read(self.entry(size.directory(), &source))
+                .is_ok_and(|bytes| Self::fresh(&bytes, &source))
+        )
+    }
+
+    pub fn store
+    (
+        &self,
+        filepath: &Path,
+        still: &StillPicture,
+        dimensions: PictureDimensions,
+        size: ThumbnailSize
+    ) -> PictureResult<()>
+    {
+        let source = Source::read(filepath).map_err(PictureError::IO)?;
+        if filepath.starts_with(&self.root)
+        {
+            return Ok(())
+        }
+        let thumbnail = scale_thumbnail(still, size.pixels())?;
+        Self::write(&self.entry(size.directory(), &source), &source, &thumbnail, dimensions)
+            .map_err(PictureError::IO)
+    }
+
+    fn fresh(bytes: &[u8], source: &Source) -> bool
+    {
+        let Ok(reader) = png::Decoder::new(bytes).read_info() else {return false};
+        let text = |keyword: &str| reader.info()
+            .uncompressed_latin1_text
+            .iter()
+            .find(|chunk| chunk.keyword == keyword)
+            .map(|chunk| chunk.text.trim().to_owned());
+        text("Thumb::URI").as_ref() == Some(&source.uri) &&
+            text("Thumb::MTime").and_then(|t| t.parse().ok()) == Some(source.modified) &&
+            text("Thumb::Size").is_none_or(|t| t.parse().ok() == Some(source.size))
+    }
+
+    fn read(entry: &Path, source: &Source) -> PictureResult<StillPicture>
+    {
+        let bytes = fs::read(entry).map_err(PictureError::IO)?;
+        if !Self::fresh(&bytes, source)
+        {
+            return Err(PictureError::IO(io::Error::new(io::ErrorKind::InvalidData, "Stale thumbnail")))
+        }
+        image::load_from_memory_with_format(&bytes, image::ImageFormat::Png)
+            .map_err(PictureError::ImageError)
+            .and_then(|d| StillPicture::try_from((lcms2::Profile::new_srgb(), d)))
+    }
+
+    fn write
+    (
+        entry: &Path,
+        source: &Source,
+        thumbnail: &image::RgbaImage,
+        [width, height]: PictureDimensions
+    ) -> io::Result<()>
+    {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(entry.parent().unwrap())?;
+        let temporary = entry.with_extension(format!("png.{}", std::process::id()));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(&temporary)?;
+        let mut encoder = png::Encoder::new
+        (
+            io::BufWriter::new(file),
+            thumbnail.width(),
+            thumbnail.height()
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let chunks =
+        [
+            ("Thumb::URI", source.uri.clone()),
+            ("Thumb::MTime", source.modified.to_string()),
+            ("Thumb::Size", source.size.to_string()),
+            ("Thumb::Image::Width", width.to_string()),
+            ("Thumb::Image::Height", height.to_string()),
+            ("Software", Self::SOFTWARE.to_owned())
+        ];
+        let result = chunks.into_iter()
+            .try_for_each(|(keyword, text)| encoder.add_text_chunk(keyword.to_owned(), text))
+            .and_then(|_| encoder.write_header())
+            .and_then
+            (
+                |mut writer| writer.write_image_data(thumbnail.as_raw())
+                    .and_then(|_| writer.finish())
+            )
+            .map_err(io::Error::from)
+            .and_then(|_| fs::rename(&temporary, entry));
+        if result.is_err()
+        {
+            let _ = fs::remove_file(&temporary);
+        }
+        result
+    }
+}
+
+// ------------------------------------------------------------
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn md5_matches_rfc_1321_test_suite()
+    {
+        let suite =
+        [
+            ("", "d41d8cd98f00b204e9800998ecf8427e"),
+            ("a", "0cc175b9c0f1b6a831c399e269772661"),
+            ("abc", "900150983cd24fb0d6963f7d28e17f72"),
+            ("message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+            ("abcdefghijklmnopqrstuvwxyz", "c3fcd3d76192e4007dfb496cca67e13b"),
+            (
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "d174ab98d277d9f5a5611c2c9f419d9f"
+            ),
+            (
+                "12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "57edf4a22be3c955ac49da2e2107b67a"
+            )
+        ];
+        for (message, expected) in suite
+        {
+            let digest: String = md5(message.as_bytes()).iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            assert_eq!(digest, expected, "md5({message:?})")
+        }
+    }
+}
@@ -1,5 +1,5 @@
 
-use std::time::*;
+use {std::{time::*, sync::{Arc, Mutex, OnceLock}}, winit::event_loop::EventLoopProxy};
 
 // ------------------------------------------------------------
 
@@ -23,3 +23,94 @@ pub fn spin(interval: Duration) -> ()
     let now = Instant::now();
     while now.elapsed() < interval {}
 }
+
+// ------------------------------------------------------------
+
+#[cfg(target_os = "windows")]
+fn query_physical_memory() -> Option<u64>
+{
+    use windows::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+    let mut status = MEMORYSTATUSEX
+    {
+        dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32,
+        ..Default::default()
+    };
+    unsafe{GlobalMemoryStatusEx(&mut status)}
+        .as_bool()
+        .then_some(status.ullTotalPhys)
+}
+
+#[cfg(target_os = "linux")]
+fn query_physical_memory() -> Option<u64>
+{
+    std::fs::read_to_string("/proc/meminfo").ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>().ok()?
+        .checked_mul(1024)
+}
+
+#[cfg(target_os = "macos")]
+fn query_physical_memory() -> Option<u64>
+{
+    let output = std::process::Command::new("sysctl")
+        .args(["-n", "hw.memsize"])
+        .output().ok()?;
+    String::from_utf8(output.stdout).ok()?
+        .trim()
+        .parse().ok()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn query_physical_memory() -> Option<u64>
+{
+    None
+}
+
+pub fn physical_memory() -> Option<u64>
+{
+    static MEMORY: OnceLock<Option<u64>> = OnceLock::new();
+    *MEMORY.get_or_init(query_physical_memory)
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct Waker(EventLoopProxy<()>);
+
+impl Waker
+{
+    pub fn new(proxy: EventLoopProxy<()>) -> Self
+    {
+        Self(proxy)
+    }
+
+    pub fn wake(&self)
+    {
+        let _ = self.0.send_event(());
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Default)]
+pub struct SharedWaker(Arc<Mutex<Option<Waker>>>);
+
+impl SharedWaker
+{
+    pub fn set(&self, waker: Waker)
+    {
+        *self.0.lock().unwrap() = Some(waker)
+    }
+
+    pub fn wake(&self)
+    {
+        if let Some(waker) = self.0.lock().unwrap().as_ref()
+        {
+            waker.wake()
+        }
+    }
+}